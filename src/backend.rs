@@ -0,0 +1,131 @@
+//! Output formats. A backend renders the resolved tree returned by
+//! `SemanticAnalyzer::check_program`, so every `AST::VarUse` already carries
+//! its value and backends never deal with scopes.
+
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use crate::Options;
+use crate::syntax_analyzer::AST;
+
+pub mod html;
+pub mod latex;
+pub mod markdown;
+pub mod text;
+
+pub use self::html::{HtmlBackend, HtmlStyle, Theme};
+pub use self::latex::LatexBackend;
+pub use self::markdown::MarkdownBackend;
+pub use self::text::TextBackend;
+
+pub trait Backend {
+    /// Writes `program`, an `AST::Program`, to `out`.
+    fn emit(&mut self, program: &AST, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// The output format, chosen on the command line with `--target`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Target {
+    #[default]
+    Html,
+    Markdown,
+    Text,
+    Latex,
+}
+
+impl Target {
+    /// Extension of the file the CLI writes, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Target::Html => "html",
+            Target::Markdown => "md",
+            Target::Text => "txt",
+            Target::Latex => "tex",
+        }
+    }
+
+    /// The backend for this format, configured from `options`.
+    pub fn backend(&self, options: &Options) -> Box<dyn Backend> {
+        match self {
+            Target::Html => Box::new(HtmlBackend::new(options)),
+            Target::Markdown => Box::new(MarkdownBackend::new()),
+            Target::Text => Box::new(TextBackend::new(options.width, options.color)),
+            Target::Latex => Box::new(LatexBackend::new()),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "html" => Ok(Target::Html),
+            "markdown" | "md" => Ok(Target::Markdown),
+            "text" | "txt" => Ok(Target::Text),
+            "latex" | "tex" => Ok(Target::Latex),
+            _ => Err(format!(
+                "unknown target `{}`, expected `html`, `markdown`, `text` or `latex`",
+                s
+            )),
+        }
+    }
+}
+
+/// Text with each run of whitespace, line breaks included, made one space, for
+/// formats where a line break in running text would mean something else.
+pub(crate) fn collapse_spaces(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut space = false;
+    for c in s.chars() {
+        if c.is_whitespace() {
+            space = true;
+        } else {
+            if space {
+                out.push(' ');
+            }
+            space = false;
+            out.push(c);
+        }
+    }
+    if space {
+        out.push(' ');
+    }
+    out
+}
+
+pub(crate) fn trim_trailing_spaces(out: &mut String) {
+    let len = out.trim_end_matches(' ').len();
+    out.truncate(len);
+}
+
+/// Ends the current line, unless one was just ended.
+pub(crate) fn line_break(out: &mut String) {
+    trim_trailing_spaces(out);
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Leaves a blank line, which ends a paragraph in Markdown and LaTeX.
+pub(crate) fn block_break(out: &mut String) {
+    line_break(out);
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// Makes text safe inside `<!-- -->`: a comment body may not contain `--`,
+/// so consecutive dashes are split apart, which also defuses `-->` and `<!--`.
+pub(crate) fn escape_comment(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut prev = ' ';
+    for c in s.chars() {
+        if c == '-' && prev == '-' {
+            out.push(' ');
+        }
+        out.push(c);
+        prev = c;
+    }
+    out
+}
@@ -0,0 +1,536 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use crate::Options;
+use crate::backend::Backend;
+use crate::syntax_analyzer::AST;
+
+use self::dom::Node;
+
+pub use self::theme::Theme;
+
+mod dom;
+mod theme;
+
+//title used when the source has no head, since HTML5 requires one
+const UNTITLED: &str = "Untitled";
+
+//the element for each heading level, from 1
+const HEADINGS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// How the HTML backend lays out its markup, chosen with `--html-style`.
+/// Every style renders the same text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HtmlStyle {
+    /// Line breaks after block elements and nothing else.
+    #[default]
+    Plain,
+    /// Every block on its own line, indented by nesting depth.
+    Pretty,
+    /// No optional whitespace and no comments.
+    Minified,
+}
+
+impl FromStr for HtmlStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(HtmlStyle::Plain),
+            "pretty" => Ok(HtmlStyle::Pretty),
+            "minified" => Ok(HtmlStyle::Minified),
+            _ => Err(format!(
+                "unknown HTML style `{}`, expected `plain`, `pretty` or `minified`",
+                s
+            )),
+        }
+    }
+}
+
+/// Renders the tree as an HTML5 document. The head is always written, with
+/// the charset and viewport set, and all content goes in the body.
+///
+/// Stylesheets come from the theme, then the head's `#GIMMEH STYLE`s, then
+/// `--css`, so later ones win. Each is linked unless `inline_css` is set, in
+/// which case local files are read and copied into a `<style>`.
+#[derive(Debug, Clone, Default)]
+pub struct HtmlBackend {
+    style: HtmlStyle,
+    theme: Option<Theme>,
+    css: Vec<String>,
+    inline_css: bool,
+    source_dir: Option<PathBuf>,
+}
+
+impl HtmlBackend {
+    pub fn new(options: &Options) -> Self {
+        HtmlBackend {
+            style: options.html_style,
+            theme: options.theme,
+            css: options.css.clone(),
+            inline_css: options.inline_css,
+            source_dir: options.source_dir.clone(),
+        }
+    }
+
+    fn document(&self, program: &AST) -> io::Result<Vec<Node>> {
+        let parts: &[AST] = match program {
+            AST::Program { parts, .. } => parts,
+            other => std::slice::from_ref(other),
+        };
+        let head = parts.iter().find(|p| matches!(p, AST::Head { .. }));
+        let lang = match head {
+            Some(AST::Head {
+                language: Some(language),
+                ..
+            }) if !language.trim().is_empty() => language.trim(),
+            _ => "en",
+        };
+        let mut body = vec![Node::newline()];
+        for p in parts {
+            self.render(p, &mut body);
+        }
+        let html = Node::element(
+            "html",
+            vec![
+                Node::newline(),
+                self.head(head)?,
+                Node::newline(),
+                Node::element("body", body),
+                Node::newline(),
+            ],
+        )
+        .with_attr("lang", Some(lang));
+        Ok(vec![Node::Doctype, Node::newline(), html, Node::newline()])
+    }
+
+    /*Generates HTML code based on the Parse Tree. For each lolcode element,
+    it will append an equivalent HTML elements to a output string.
+    */
+    fn render(&self, node: &AST, out: &mut Vec<Node>) {
+        match node {
+            AST::Program { parts, .. } => {
+                for p in parts {
+                    self.render(p, out);
+                }
+            }
+
+            AST::Comment(txt, _) => {
+                out.push(Node::Comment(txt.clone()));
+                out.push(Node::newline());
+            }
+
+            //written by `head` before the body
+            AST::Head { .. } => {}
+
+            AST::Paragraph { items, .. } => self.flow(items, items.iter().any(is_block), out),
+            AST::Heading { level, items, .. } => {
+                let mut children = Vec::new();
+                for it in items {
+                    self.render(it, &mut children);
+                }
+                let name = HEADINGS[(*level as usize).clamp(1, HEADINGS.len()) - 1];
+                out.push(Node::element(name, children));
+                out.push(Node::newline());
+            }
+            AST::List {
+                ordered,
+                start,
+                items,
+                ..
+            } => {
+                let mut children = vec![Node::newline()];
+                for it in items {
+                    self.render(it, &mut children);
+                }
+                if !*ordered {
+                    out.push(Node::element("ul", children));
+                } else if let Some(n) = start.filter(|&n| n != 1) {
+                    out.push(Node::element("ol", children).with_attr("start", Some(&n.to_string())));
+                } else {
+                    out.push(Node::element("ol", children));
+                }
+                out.push(Node::newline());
+            }
+            AST::ListItem { items, .. } => {
+                let mut children = Vec::new();
+                for it in items {
+                    self.render(it, &mut children);
+                }
+                out.push(Node::element("li", children));
+                out.push(Node::newline());
+            }
+
+            AST::CodeBlock { lang, text, .. } => {
+                let class = lang.as_ref().map(|l| format!("language-{}", l));
+                let mut code = Node::element("code", vec![Node::text(text)]);
+                if let Some(class) = &class {
+                    code = code.with_attr("class", Some(class));
+                }
+                out.push(Node::element("pre", vec![code]));
+                out.push(Node::newline());
+            }
+
+            //leading header rows make up the <thead>, everything after the <tbody>
+            AST::Table { rows, .. } => {
+                let split = rows
+                    .iter()
+                    .position(|r| !matches!(r, AST::Row { header: true, .. }))
+                    .unwrap_or(rows.len());
+                let mut children = vec![Node::newline()];
+                for (name, part) in [("thead", &rows[..split]), ("tbody", &rows[split..])] {
+                    if part.is_empty() {
+                        continue;
+                    }
+                    let mut section = vec![Node::newline()];
+                    for row in part {
+                        self.render(row, &mut section);
+                    }
+                    children.push(Node::element(name, section));
+                    children.push(Node::newline());
+                }
+                out.push(Node::element("table", children));
+                out.push(Node::newline());
+            }
+            AST::Row { header, cells, .. } => {
+                let mut children = Vec::new();
+                for cell in cells {
+                    let mut content = Vec::new();
+                    match cell {
+                        AST::Cell { items, .. } => {
+                            for it in items {
+                                self.render(it, &mut content);
+                            }
+                        }
+                        other => self.render(other, &mut content),
+                    }
+                    children.push(Node::element(if *header { "th" } else { "td" }, content));
+                }
+                out.push(Node::element("tr", children));
+                out.push(Node::newline());
+            }
+            AST::Cell { items, .. } => {
+                let mut children = Vec::new();
+                for it in items {
+                    self.render(it, &mut children);
+                }
+                out.push(Node::element("td", children));
+            }
+
+            //the attribution goes in a <footer>, which is part of the quote
+            AST::Quote { items, cite, .. } => {
+                let mut children = vec![Node::newline()];
+                self.flow(items, true, &mut children);
+                if !cite.is_empty() {
+                    let cite = self.phrase("cite", cite);
+                    children.push(Node::element("footer", vec![cite]));
+                    children.push(Node::newline());
+                }
+                out.push(Node::element("blockquote", children));
+                out.push(Node::newline());
+            }
+            AST::Rule(_) => {
+                out.push(Node::element("hr", Vec::new()));
+                out.push(Node::newline());
+            }
+
+            AST::Bold(items, _) => out.push(self.phrase("b", items)),
+            AST::Italics(items, _) => out.push(self.phrase("i", items)),
+            AST::Audio(url, _) => {
+                let source = Node::element("source", Vec::new()).with_attr("src", Some(url));
+                out.push(
+                    Node::element("audio", vec![Node::newline(), source, Node::newline()])
+                        .with_attr("controls", None),
+                );
+            }
+            AST::Video(url, _) => {
+                out.push(Node::element("iframe", Vec::new()).with_attr("src", Some(url)));
+                out.push(Node::newline());
+            }
+            AST::Link { url, items, .. } => {
+                let mut children = Vec::new();
+                for it in items {
+                    self.render(it, &mut children);
+                }
+                if children.is_empty() {
+                    children.push(Node::text(url));
+                }
+                out.push(Node::element("a", children).with_attr("href", Some(url)));
+            }
+            AST::Image { url, alt, .. } => {
+                out.push(
+                    Node::element("img", Vec::new())
+                        .with_attr("src", Some(url))
+                        .with_attr("alt", Some(alt)),
+                );
+            }
+            AST::Newline(_) => {
+                out.push(Node::element("br", Vec::new()));
+                out.push(Node::newline());
+            }
+
+            AST::Text(t, _) => {
+                if !t.is_empty() {
+                    out.push(Node::text(t));
+                }
+            }
+
+            //definitions produce no output; their values are already on each use
+            AST::VarDefine { .. } => {}
+
+            AST::VarUse { value, .. } => {
+                if let Some(v) = value {
+                    out.push(Node::text(v));
+                }
+            }
+        }
+    }
+
+    fn head<'a>(&self, head: Option<&'a AST>) -> io::Result<Node> {
+        let empty = Vec::new();
+        let (title, author, description, keywords, favicon, styles) = match head {
+            Some(AST::Head {
+                title,
+                author,
+                description,
+                keywords,
+                favicon,
+                styles,
+                ..
+            }) => (
+                title.trim(),
+                author.as_deref(),
+                description.as_deref(),
+                keywords,
+                favicon.as_deref(),
+                styles,
+            ),
+            _ => ("", None, None, &empty, None, &empty),
+        };
+        let given = |value: Option<&'a str>| value.map(str::trim).filter(|v| !v.is_empty());
+        let (author, description, favicon) = (given(author), given(description), given(favicon));
+        let mut children = vec![
+            Node::newline(),
+            Node::element("meta", Vec::new()).with_attr("charset", Some("utf-8")),
+            Node::newline(),
+            Node::element("meta", Vec::new())
+                .with_attr("name", Some("viewport"))
+                .with_attr("content", Some("width=device-width, initial-scale=1")),
+            Node::newline(),
+            Node::element(
+                "title",
+                vec![Node::text(if title.is_empty() { UNTITLED } else { title })],
+            ),
+            Node::newline(),
+        ];
+
+        //search engines read the named metas, link previews the Open Graph ones
+        let mut meta = |key: &'static str, name: &str, content: &str| {
+            children.push(
+                Node::element("meta", Vec::new())
+                    .with_attr(key, Some(name))
+                    .with_attr("content", Some(content)),
+            );
+            children.push(Node::newline());
+        };
+        if let Some(description) = description {
+            meta("name", "description", description);
+        }
+        if let Some(author) = author {
+            meta("name", "author", author);
+        }
+        if !keywords.is_empty() {
+            meta("name", "keywords", &keywords.join(", "));
+        }
+        if !title.is_empty() {
+            meta("property", "og:title", title);
+        }
+        if let Some(description) = description {
+            meta("property", "og:description", description);
+        }
+        if let Some(favicon) = favicon {
+            children.push(
+                Node::element("link", Vec::new())
+                    .with_attr("rel", Some("icon"))
+                    .with_attr("href", Some(favicon)),
+            );
+            children.push(Node::newline());
+        }
+
+        if let Some(theme) = self.theme {
+            children.push(style_element(theme.css()));
+            children.push(Node::newline());
+        }
+        for href in styles {
+            //a bare theme name picks the built-in stylesheet
+            let sheet = match href.parse::<Theme>() {
+                Ok(theme) => style_element(theme.css()),
+                Err(_) => self.stylesheet(href, true)?,
+            };
+            children.push(sheet);
+            children.push(Node::newline());
+        }
+        for href in &self.css {
+            children.push(self.stylesheet(href, false)?);
+            children.push(Node::newline());
+        }
+        Ok(Node::element("head", children))
+    }
+
+    //Links `href`, or with `inline_css` copies the file it names into the page.
+    //A path the source names is read from the source's directory and may not
+    //leave it, so a document cannot publish arbitrary local files; one given
+    //with `--css` is read from the working directory.
+    fn stylesheet(&self, href: &str, from_source: bool) -> io::Result<Node> {
+        if !self.inline_css || is_remote(href) {
+            return Ok(Node::element("link", Vec::new())
+                .with_attr("rel", Some("stylesheet"))
+                .with_attr("href", Some(href)));
+        }
+        if from_source && !stays_inside(Path::new(href)) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("cannot inline stylesheet {}: it is outside the source's directory", href),
+            ));
+        }
+        let path = match &self.source_dir {
+            Some(dir) if from_source => dir.join(href),
+            _ => PathBuf::from(href),
+        };
+        let css = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("cannot read stylesheet {}: {}", path.display(), e),
+            )
+        })?;
+        Ok(style_element(&css))
+    }
+
+    //an inline element around `items`
+    fn phrase(&self, name: &'static str, items: &[AST]) -> Node {
+        let mut children = Vec::new();
+        for it in items {
+            self.render(it, &mut children);
+        }
+        Node::element(name, children)
+    }
+
+    //<p> may only hold phrasing content, so a block inside a paragraph ends it
+    //and any text after the block opens a new one
+    fn flow(&self, items: &[AST], split: bool, out: &mut Vec<Node>) {
+        let mut run: Vec<&AST> = Vec::new();
+        for it in items {
+            if is_block(it) {
+                self.paragraph(&run, split, out);
+                run.clear();
+                self.render(it, out);
+            } else {
+                run.push(it);
+            }
+        }
+        self.paragraph(&run, split, out);
+    }
+
+    //Writes a run of inline nodes as a <p>. A run left empty by splitting a
+    //paragraph around a list is dropped.
+    fn paragraph(&self, items: &[&AST], split: bool, out: &mut Vec<Node>) {
+        let mut children = Vec::new();
+        for it in items {
+            self.render(it, &mut children);
+        }
+        let blank = children.iter().all(|c| match c {
+            Node::Text(t) => t.trim().is_empty(),
+            _ => false,
+        });
+        if split && blank {
+            return;
+        }
+        out.push(Node::element("p", children));
+        out.push(Node::newline());
+    }
+}
+
+fn style_element(css: &str) -> Node {
+    //the only thing that can end a <style> early is its own end tag, in any
+    //case; `\/` is a CSS escape for the same slash
+    let mut raw = String::from("\n");
+    let mut last = 0;
+    for (i, _) in css.to_ascii_lowercase().match_indices("</style") {
+        raw.push_str(&css[last..i]);
+        raw.push_str("<\\/");
+        last = i + 2;
+    }
+    raw.push_str(&css[last..]);
+    if !raw.ends_with('\n') {
+        raw.push('\n');
+    }
+    Node::element("style", vec![Node::Raw(raw)])
+}
+
+//whether `path` is relative and never climbs above where it starts
+fn stays_inside(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+fn is_remote(href: &str) -> bool {
+    href.contains("://") || href.starts_with("//")
+}
+
+/// Whether `node` renders as flow content that cannot sit inside a `<p>`.
+fn is_block(node: &AST) -> bool {
+    matches!(
+        node,
+        AST::Paragraph { .. }
+            | AST::List { .. }
+            | AST::Heading { .. }
+            | AST::Table { .. }
+            | AST::CodeBlock { .. }
+            | AST::Quote { .. }
+            | AST::Rule(_)
+    )
+}
+
+impl Backend for HtmlBackend {
+    fn emit(&mut self, program: &AST, out: &mut dyn Write) -> io::Result<()> {
+        let document = self.document(program)?;
+        let mut html = String::new();
+        match self.style {
+            HtmlStyle::Plain => dom::write_plain(&document, &mut html),
+            HtmlStyle::Pretty => dom::write_formatted(&document, true, &mut html),
+            HtmlStyle::Minified => dom::write_formatted(&document, false, &mut html),
+        }
+        out.write_all(html.as_bytes())
+    }
+}
+
+/// Escapes text placed between tags.
+fn escape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes text placed inside a double-quoted attribute value.
+fn escape_attr(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,343 @@
+//! The markup the HTML backend builds before writing it out, and the three
+//! ways of writing it: verbatim, indented and minified.
+
+use super::{escape_attr, escape_text};
+use crate::backend::escape_comment;
+
+/// A node of the generated document. Text is kept unescaped until written.
+#[derive(Debug, Clone)]
+pub(crate) enum Node {
+    Doctype,
+    Element {
+        name: &'static str,
+        /// `None` values are boolean attributes, written as the bare name.
+        attrs: Vec<(&'static str, Option<String>)>,
+        children: Vec<Node>,
+    },
+    Text(String),
+    /// Contents of a raw text element such as `<style>`, written unescaped.
+    Raw(String),
+    Comment(String),
+}
+
+impl Node {
+    pub(crate) fn element(name: &'static str, children: Vec<Node>) -> Node {
+        Node::Element {
+            name,
+            attrs: Vec::new(),
+            children,
+        }
+    }
+
+    pub(crate) fn with_attr(mut self, key: &'static str, value: Option<&str>) -> Node {
+        if let Node::Element { attrs, .. } = &mut self {
+            attrs.push((key, value.map(str::to_string)));
+        }
+        self
+    }
+
+    pub(crate) fn text(s: &str) -> Node {
+        Node::Text(s.to_string())
+    }
+
+    pub(crate) fn newline() -> Node {
+        Node::text("\n")
+    }
+
+    fn name(&self) -> Option<&'static str> {
+        match self {
+            Node::Element { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+
+    /// Whether whitespace on either side of the node is insignificant to how
+    /// the page renders, so formatting may add or remove it there.
+    fn is_block(&self) -> bool {
+        match self {
+            Node::Doctype => true,
+            Node::Element { name, .. } => BLOCK.contains(name),
+            Node::Text(_) | Node::Raw(_) | Node::Comment(_) => false,
+        }
+    }
+}
+
+//elements that start a new line when rendered, plus those of the head
+const BLOCK: &[&str] = &[
+    "html", "head", "body", "title", "meta", "link", "style", "p", "h1", "h2", "h3", "h4", "h5",
+    "h6", "ul", "ol", "li", "table", "thead", "tbody", "tr", "th", "td", "pre", "blockquote",
+    "footer", "hr",
+];
+
+//elements with no content and no end tag
+const VOID: &[&str] = &["meta", "link", "br", "source", "img", "hr"];
+
+//elements whose whitespace is rendered as written, so is never reformatted
+const PREFORMATTED: &[&str] = &["pre"];
+
+//media whose children are fallbacks rather than rendered text
+const OPAQUE: &[&str] = &["audio", "video"];
+
+fn start_tag(out: &mut String, name: &str, attrs: &[(&'static str, Option<String>)]) {
+    out.push('<');
+    out.push_str(name);
+    for (key, value) in attrs {
+        out.push(' ');
+        out.push_str(key);
+        if let Some(v) = value {
+            out.push_str("=\"");
+            out.push_str(&escape_attr(v));
+            out.push('"');
+        }
+    }
+    out.push('>');
+}
+
+fn end_tag(out: &mut String, name: &str) {
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+}
+
+/// Writes the nodes exactly as built, whitespace text included.
+pub(crate) fn write_plain(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Doctype => out.push_str("<!DOCTYPE html>"),
+            Node::Element {
+                name,
+                attrs,
+                children,
+            } => {
+                start_tag(out, name, attrs);
+                if !VOID.contains(name) {
+                    write_plain(children, out);
+                    end_tag(out, name);
+                }
+            }
+            Node::Text(t) => out.push_str(&escape_text(t)),
+            Node::Raw(r) => out.push_str(r),
+            Node::Comment(c) => {
+                out.push_str("<!-- ");
+                out.push_str(&escape_comment(c));
+                out.push_str(" -->");
+            }
+        }
+    }
+}
+
+/// Writes the nodes with whitespace normalised: runs of whitespace in text
+/// collapse to one space and none is kept next to block-level elements.
+/// The contents of `<pre>` are written as they are.
+/// `pretty` puts each block on its own line, indented two spaces per level;
+/// otherwise nothing optional is written, comments included, and the CSS in
+/// `<style>` is minified too.
+pub(crate) fn write_formatted(nodes: &[Node], pretty: bool, out: &mut String) {
+    let mut f = Formatter { pretty, out };
+    f.block_children(nodes, 0);
+    if pretty && !f.out.is_empty() {
+        f.out.push('\n');
+    }
+}
+
+struct Formatter<'a> {
+    pretty: bool,
+    out: &'a mut String,
+}
+
+//Collapsing state for a run of inline content. A space is only written once
+//something visible follows it, so runs are trimmed at both ends.
+#[derive(Default)]
+struct Spacing {
+    started: bool,
+    pending: bool,
+}
+
+impl Spacing {
+    fn flush(&mut self, out: &mut String) {
+        if self.pending && self.started {
+            out.push(' ');
+        }
+        self.pending = false;
+        self.started = true;
+    }
+}
+
+impl Formatter<'_> {
+    fn line(&mut self, depth: usize) {
+        if self.pretty {
+            if !self.out.is_empty() {
+                self.out.push('\n');
+            }
+            self.out.push_str(&"  ".repeat(depth));
+        }
+    }
+
+    //Children of a block element: each block child and each run of inline
+    //content between them goes on its own line.
+    fn block_children(&mut self, children: &[Node], depth: usize) {
+        let mut run: Vec<&Node> = Vec::new();
+        for child in children {
+            if child.is_block() {
+                self.run(&run, depth);
+                run.clear();
+                self.line(depth);
+                self.block(child, depth);
+            } else {
+                run.push(child);
+            }
+        }
+        self.run(&run, depth);
+    }
+
+    fn run(&mut self, run: &[&Node], depth: usize) {
+        let text = self.inline_run(run);
+        if !text.is_empty() {
+            self.line(depth);
+            self.out.push_str(&text);
+        }
+    }
+
+    fn inline_run(&self, run: &[&Node]) -> String {
+        let mut text = String::new();
+        let mut spacing = Spacing::default();
+        for node in run {
+            self.inline(node, &mut text, &mut spacing);
+        }
+        text
+    }
+
+    fn block(&mut self, node: &Node, depth: usize) {
+        match node {
+            Node::Doctype => self.out.push_str("<!DOCTYPE html>"),
+            Node::Element {
+                name,
+                attrs,
+                children,
+            } => {
+                start_tag(self.out, name, attrs);
+                if VOID.contains(name) {
+                    return;
+                }
+                if PREFORMATTED.contains(name) {
+                    write_plain(children, self.out);
+                } else if children.iter().any(Node::is_block) {
+                    self.block_children(children, depth + 1);
+                    self.line(depth);
+                } else {
+                    let refs: Vec<&Node> = children.iter().collect();
+                    let text = self.inline_run(&refs);
+                    self.out.push_str(&text);
+                }
+                end_tag(self.out, name);
+            }
+            Node::Text(_) | Node::Raw(_) | Node::Comment(_) => {}
+        }
+    }
+
+    fn inline(&self, node: &Node, out: &mut String, spacing: &mut Spacing) {
+        match node {
+            Node::Text(t) => {
+                for c in t.chars() {
+                    if c.is_ascii_whitespace() {
+                        spacing.pending = true;
+                    } else {
+                        spacing.flush(out);
+                        let mut buf = [0; 4];
+                        out.push_str(&escape_text(c.encode_utf8(&mut buf)));
+                    }
+                }
+            }
+            Node::Raw(r) => {
+                spacing.flush(out);
+                if self.pretty {
+                    out.push_str(r);
+                } else {
+                    out.push_str(&minify_css(r));
+                }
+            }
+            Node::Comment(c) => {
+                if self.pretty {
+                    out.push_str("<!-- ");
+                    out.push_str(&escape_comment(c));
+                    out.push_str(" -->");
+                }
+            }
+            Node::Element {
+                name,
+                attrs,
+                children,
+            } => {
+                spacing.flush(out);
+                start_tag(out, name, attrs);
+                if VOID.contains(name) {
+                    return;
+                }
+                if OPAQUE.contains(name) {
+                    //fallback whitespace is not part of the surrounding text
+                    let mut inner = Spacing::default();
+                    for child in children.iter().filter(|c| c.name().is_some()) {
+                        self.inline(child, out, &mut inner);
+                    }
+                } else {
+                    for child in children {
+                        self.inline(child, out, spacing);
+                    }
+                }
+                end_tag(out, name);
+            }
+            Node::Doctype => {}
+        }
+    }
+}
+
+//characters that whitespace around can be dropped from in CSS. Only the space
+//after a `:` can go, as `p :first-child` is a different selector from
+//`p:first-child`.
+const CSS_PUNCTUATION: &[char] = &['{', '}', ';', ','];
+
+//CSS without comments, and with whitespace only where it separates two words,
+//as in a selector or a shorthand value. Strings are copied unchanged.
+fn minify_css(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut space = false;
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_whitespace() {
+            space = true;
+            continue;
+        }
+        if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut last = ' ';
+            for c in chars.by_ref() {
+                if last == '*' && c == '/' {
+                    break;
+                }
+                last = c;
+            }
+            space = true;
+            continue;
+        }
+        let after_punctuation = out.ends_with(CSS_PUNCTUATION) || out.ends_with(':');
+        if space && !out.is_empty() && !after_punctuation && !CSS_PUNCTUATION.contains(&c) {
+            out.push(' ');
+        }
+        space = false;
+        if c == '}' && out.ends_with(';') {
+            out.pop();
+        }
+        out.push(c);
+        if c == '"' || c == '\'' {
+            while let Some(s) = chars.next() {
+                out.push(s);
+                if s == '\\' {
+                    out.extend(chars.next());
+                } else if s == c {
+                    break;
+                }
+            }
+        }
+    }
+    out
+}
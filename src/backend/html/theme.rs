@@ -0,0 +1,44 @@
+use std::str::FromStr;
+
+/// A stylesheet compiled into the binary, chosen with `--theme` or by naming
+/// it in `#GIMMEH STYLE`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Classic,
+    Dark,
+    Lolcat,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Classic, Theme::Dark, Theme::Lolcat];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Classic => "classic",
+            Theme::Dark => "dark",
+            Theme::Lolcat => "lolcat",
+        }
+    }
+
+    pub fn css(&self) -> &'static str {
+        match self {
+            Theme::Classic => include_str!("themes/classic.css"),
+            Theme::Dark => include_str!("themes/dark.css"),
+            Theme::Lolcat => include_str!("themes/lolcat.css"),
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Theme::ALL
+            .into_iter()
+            .find(|t| t.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<String> = Theme::ALL.iter().map(|t| format!("`{}`", t.name())).collect();
+                format!("unknown theme `{}`, expected one of {}", s, names.join(", "))
+            })
+    }
+}
@@ -0,0 +1,356 @@
+use std::io::{self, Write};
+
+use crate::backend::{Backend, block_break, collapse_spaces, line_break, trim_trailing_spaces};
+use crate::syntax_analyzer::AST;
+
+//the counter of each level of nested enumerate, from the outermost
+const COUNTERS: [&str; 4] = ["enumi", "enumii", "enumiii", "enumiv"];
+
+//the sectioning command for each heading level, from 1; an article has no
+//level below \subparagraph
+const SECTIONS: [&str; 6] = [
+    "\\section",
+    "\\subsection",
+    "\\subsubsection",
+    "\\paragraph",
+    "\\subparagraph",
+    "\\subparagraph",
+];
+
+/// Renders the tree as a standalone LaTeX `article`, ready for `pdflatex`.
+#[derive(Debug, Clone, Default)]
+pub struct LatexBackend {
+    //how many enumerate environments the renderer is inside, which decides
+    //the counter a start number is set on
+    enumerate_depth: usize,
+}
+
+impl LatexBackend {
+    pub fn new() -> Self {
+        LatexBackend::default()
+    }
+
+    fn render(&mut self, node: &AST, out: &mut String) {
+        match node {
+            AST::Program { parts, .. } => {
+                out.push_str("\\documentclass{article}\n");
+                out.push_str("\\usepackage[T1]{fontenc}\n");
+                out.push_str("\\usepackage[utf8]{inputenc}\n");
+                out.push_str("\\usepackage{hyperref}\n");
+                //the title belongs in the preamble, wherever the head was written
+                let head = parts.iter().find(|p| matches!(p, AST::Head { .. }));
+                if let Some(AST::Head {
+                    title,
+                    author,
+                    description,
+                    keywords,
+                    ..
+                }) = head
+                {
+                    let title = title.trim();
+                    let author = author.as_deref().unwrap_or("").trim();
+                    //without a title there is nothing for \maketitle to show
+                    if !title.is_empty() {
+                        out.push_str("\\title{");
+                        out.push_str(&escape_text(title));
+                        out.push_str("}\n\\author{");
+                        out.push_str(&escape_text(author));
+                        out.push_str("}\n\\date{}\n");
+                    }
+                    //the same metadata, in the PDF's document properties
+                    let mut properties = Vec::new();
+                    if !title.is_empty() {
+                        properties.push(format!("pdftitle={{{}}}", escape_text(title)));
+                    }
+                    if !author.is_empty() {
+                        properties.push(format!("pdfauthor={{{}}}", escape_text(author)));
+                    }
+                    if let Some(description) = description {
+                        let description = escape_text(description.trim());
+                        properties.push(format!("pdfsubject={{{}}}", description));
+                    }
+                    if !keywords.is_empty() {
+                        let keywords = escape_text(&keywords.join(", "));
+                        properties.push(format!("pdfkeywords={{{}}}", keywords));
+                    }
+                    if !properties.is_empty() {
+                        out.push_str(&format!("\\hypersetup{{{}}}\n", properties.join(", ")));
+                    }
+                }
+                out.push_str("\n\\begin{document}\n");
+                for p in parts {
+                    match p {
+                        //media outside a paragraph gets a paragraph of its own
+                        AST::Audio(..) | AST::Video(..) => {
+                            block_break(out);
+                            self.render(p, out);
+                            block_break(out);
+                        }
+                        _ => self.render(p, out),
+                    }
+                }
+                line_break(out);
+                out.push_str("\\end{document}\n");
+            }
+
+            AST::Comment(txt, _) => {
+                line_break(out);
+                for line in txt.trim().lines() {
+                    out.push_str("% ");
+                    out.push_str(line.trim_end());
+                    out.push('\n');
+                }
+            }
+
+            AST::Head { title, .. } => {
+                if !title.trim().is_empty() {
+                    block_break(out);
+                    out.push_str("\\maketitle");
+                    block_break(out);
+                }
+            }
+
+            AST::Paragraph { items, .. } => {
+                block_break(out);
+                for it in items {
+                    self.render(it, out);
+                }
+                block_break(out);
+            }
+            AST::Heading { level, items, .. } => {
+                block_break(out);
+                out.push_str(SECTIONS[(*level as usize).clamp(1, SECTIONS.len()) - 1]);
+                out.push_str("*{");
+                for it in items {
+                    self.render(it, out);
+                }
+                trim_trailing_spaces(out);
+                out.push('}');
+                block_break(out);
+            }
+            AST::List {
+                ordered,
+                start,
+                items,
+                ..
+            } => {
+                //a list environment with no \item is an error in LaTeX
+                if items.is_empty() {
+                    return;
+                }
+                let env = if *ordered { "enumerate" } else { "itemize" };
+                line_break(out);
+                out.push_str(&format!("\\begin{{{}}}\n", env));
+                if *ordered {
+                    self.enumerate_depth += 1;
+                    //the counter is stepped before each item is numbered
+                    let counter = COUNTERS.get(self.enumerate_depth - 1);
+                    if let (Some(counter), Some(n)) = (counter, start.filter(|&n| n != 1)) {
+                        out.push_str(&format!("\\setcounter{{{}}}{{{}}}\n", counter, n as i64 - 1));
+                    }
+                }
+                for it in items {
+                    self.render(it, out);
+                }
+                if *ordered {
+                    self.enumerate_depth -= 1;
+                }
+                line_break(out);
+                out.push_str(&format!("\\end{{{}}}\n", env));
+            }
+            AST::ListItem { items, .. } => {
+                line_break(out);
+                out.push_str("\\item ");
+                for it in items {
+                    self.render(it, out);
+                }
+                line_break(out);
+            }
+
+            //verbatim ends at the first \end{verbatim}, so one in the code is broken up
+            AST::CodeBlock { text, .. } => {
+                line_break(out);
+                out.push_str("\\begin{verbatim}\n");
+                out.push_str(&text.replace("\\end{verbatim}", "\\end {verbatim}"));
+                out.push_str("\n\\end{verbatim}\n");
+            }
+
+            //a rule under the leading header rows sets them apart
+            AST::Table { rows, .. } => {
+                let columns = rows
+                    .iter()
+                    .map(|r| match r {
+                        AST::Row { cells, .. } => cells.len(),
+                        _ => 0,
+                    })
+                    .max()
+                    .unwrap_or(0);
+                if columns == 0 {
+                    return;
+                }
+                block_break(out);
+                out.push_str(&format!("\\begin{{tabular}}{{{}}}\n", "l".repeat(columns)));
+                let mut in_header = true;
+                for (i, row) in rows.iter().enumerate() {
+                    if in_header && !matches!(row, AST::Row { header: true, .. }) {
+                        if i > 0 {
+                            out.push_str("\\hline\n");
+                        }
+                        in_header = false;
+                    }
+                    self.render(row, out);
+                }
+                out.push_str("\\end{tabular}");
+                block_break(out);
+            }
+            AST::Row { cells, .. } => {
+                let mut line: Vec<String> = Vec::new();
+                for cell in cells {
+                    let mut text = String::new();
+                    self.render(cell, &mut text);
+                    line.push(text.trim().to_string());
+                }
+                out.push_str(&line.join(" & "));
+                out.push_str(" \\\\\n");
+            }
+            AST::Cell { items, .. } => {
+                for it in items {
+                    self.render(it, out);
+                }
+            }
+
+            AST::Quote { items, cite, .. } => {
+                line_break(out);
+                out.push_str("\\begin{quote}\n");
+                for it in items {
+                    self.render(it, out);
+                }
+                //the attribution is set flush right on a line of its own
+                if !cite.is_empty() {
+                    block_break(out);
+                    out.push_str("\\hfill---~");
+                    for it in cite {
+                        self.render(it, out);
+                    }
+                }
+                line_break(out);
+                out.push_str("\\end{quote}\n");
+            }
+            AST::Rule(_) => {
+                block_break(out);
+                out.push_str("\\noindent\\rule{\\linewidth}{0.4pt}");
+                block_break(out);
+            }
+
+            AST::Bold(items, _) => self.command(out, "\\textbf", items),
+            AST::Italics(items, _) => self.command(out, "\\textit", items),
+            AST::Audio(url, _) => footnote_url(out, "Audio", url),
+            AST::Video(url, _) => footnote_url(out, "Video", url),
+            AST::Link { url, items, .. } => {
+                out.push_str("\\href{");
+                out.push_str(&escape_url(url.trim()));
+                out.push_str("}{");
+                if items.is_empty() {
+                    out.push_str("\\url{");
+                    out.push_str(&escape_url(url.trim()));
+                    out.push('}');
+                }
+                for it in items {
+                    self.render(it, out);
+                }
+                trim_trailing_spaces(out);
+                out.push('}');
+            }
+            //a remote picture cannot be included, so it is named and pointed to
+            AST::Image { url, alt, .. } => {
+                let alt = escape_text(alt.trim());
+                footnote_url(out, if alt.is_empty() { "Image" } else { &alt }, url);
+            }
+            AST::Newline(_) => {
+                //`\\` with nothing before it on the line is an error
+                if !at_paragraph_start(out) {
+                    trim_trailing_spaces(out);
+                    out.push_str(" \\\\\n");
+                }
+            }
+
+            //a blank line in the source would end the paragraph
+            AST::Text(t, _) => out.push_str(&escape_text(&collapse_spaces(t))),
+
+            AST::VarDefine { .. } => {}
+
+            AST::VarUse { value, .. } => {
+                if let Some(v) = value {
+                    out.push_str(&escape_text(v));
+                }
+            }
+        }
+    }
+
+    //`name{...}` around inline content, with no space before the brace
+    fn command(&mut self, out: &mut String, name: &str, items: &[AST]) {
+        out.push_str(name);
+        out.push('{');
+        for it in items {
+            self.render(it, out);
+        }
+        trim_trailing_spaces(out);
+        out.push('}');
+    }
+}
+
+impl Backend for LatexBackend {
+    fn emit(&mut self, program: &AST, out: &mut dyn Write) -> io::Result<()> {
+        let mut tex = String::new();
+        self.render(program, &mut tex);
+        out.write_all(tex.as_bytes())
+    }
+}
+
+fn footnote_url(out: &mut String, label: &str, url: &str) {
+    out.push_str(label);
+    out.push_str("\\footnote{\\url{");
+    out.push_str(&escape_url(url.trim()));
+    out.push_str("}}");
+}
+
+/// Whether nothing has been written on the current line but `\item`, as at
+/// the start of the document, after a blank line, an environment or a break.
+fn at_paragraph_start(out: &str) -> bool {
+    let line = out.rsplit('\n').next().unwrap_or("").trim();
+    line.is_empty() || line == "\\item"
+}
+
+/// Escapes LaTeX's special characters so they print as themselves.
+fn escape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `\url` reads its argument verbatim, except that inside another command's
+/// argument (here `\footnote`) `%`, `#` and unbalanced braces must be escaped.
+fn escape_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            '%' | '#' | '{' | '}' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,436 @@
+use std::io::{self, Write};
+
+use crate::backend::{self, Backend, collapse_spaces, escape_comment, trim_trailing_spaces};
+use crate::syntax_analyzer::AST;
+
+/// Renders the tree as CommonMark, with the title and other head metadata in
+/// YAML front matter for static site generators.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownBackend {
+    //the lists the renderer is inside, innermost last: the number of the next
+    //item of an ordered list, or None for bullets
+    lists: Vec<Option<u32>>,
+    //the width of the marker of each item the renderer is inside, which its
+    //continuation lines and nested lists are indented by
+    markers: Vec<usize>,
+}
+
+impl MarkdownBackend {
+    pub fn new() -> Self {
+        MarkdownBackend::default()
+    }
+
+    fn render(&mut self, node: &AST, out: &mut String) {
+        match node {
+            AST::Program { parts, .. } => {
+                //front matter is only recognised at the very top of the file
+                for p in parts {
+                    if let AST::Head {
+                        title,
+                        author,
+                        description,
+                        keywords,
+                        language,
+                        ..
+                    } = p
+                    {
+                        let mut fields = Vec::new();
+                        let title = Some(title).filter(|t| !t.trim().is_empty());
+                        for (key, value) in [
+                            ("title", title),
+                            ("author", author.as_ref()),
+                            ("description", description.as_ref()),
+                            ("lang", language.as_ref()),
+                        ] {
+                            if let Some(v) = value {
+                                fields.push(format!("{}: {}\n", key, yaml_string(v)));
+                            }
+                        }
+                        if !keywords.is_empty() {
+                            let keywords: Vec<String> =
+                                keywords.iter().map(|k| yaml_string(k)).collect();
+                            fields.push(format!("keywords: [{}]\n", keywords.join(", ")));
+                        }
+                        if !fields.is_empty() {
+                            out.push_str("---\n");
+                            out.push_str(&fields.concat());
+                            out.push_str("---\n\n");
+                        }
+                    }
+                }
+                for p in parts {
+                    match p {
+                        //media outside a paragraph gets a line of its own
+                        AST::Audio(..) | AST::Video(..) => {
+                            block_break(out);
+                            self.render(p, out);
+                            block_break(out);
+                        }
+                        _ => self.render(p, out),
+                    }
+                }
+            }
+
+            AST::Comment(txt, _) => {
+                block_break(out);
+                out.push_str("<!-- ");
+                out.push_str(&escape_comment(txt));
+                out.push_str(" -->");
+                block_break(out);
+            }
+
+            AST::Head { title, .. } => {
+                if !title.trim().is_empty() {
+                    block_break(out);
+                    out.push_str("# ");
+                    out.push_str(&escape_text(title, false));
+                    block_break(out);
+                }
+            }
+
+            AST::Paragraph { items, .. } => {
+                block_break(out);
+                for it in items {
+                    self.render(it, out);
+                }
+                block_break(out);
+            }
+            AST::Heading { level, items, .. } => {
+                block_break(out);
+                out.push_str(&"#".repeat((*level as usize).clamp(1, 6)));
+                out.push(' ');
+                for it in items {
+                    self.render(it, out);
+                }
+                block_break(out);
+            }
+            AST::List {
+                ordered,
+                start,
+                items,
+                ..
+            } => {
+                if self.lists.is_empty() {
+                    block_break(out);
+                } else {
+                    line_break(out);
+                }
+                self.lists.push(ordered.then(|| start.unwrap_or(1)));
+                for it in items {
+                    self.render(it, out);
+                }
+                self.lists.pop();
+                //text after a list would otherwise continue its last item
+                if self.lists.is_empty() {
+                    block_break(out);
+                }
+            }
+            AST::ListItem { items, .. } => {
+                line_break(out);
+                out.push_str(&self.indent());
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "- ".to_string(),
+                };
+                out.push_str(&marker);
+                self.markers.push(marker.len());
+                for it in items {
+                    self.render(it, out);
+                }
+                self.markers.pop();
+                line_break(out);
+            }
+
+            AST::CodeBlock { lang, text, .. } => {
+                //the fence must be longer than any run of backticks in the code
+                let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+                let fence = "`".repeat(longest.max(2) + 1);
+                block_break(out);
+                out.push_str(&fence);
+                out.push_str(lang.as_deref().unwrap_or(""));
+                out.push('\n');
+                if !text.is_empty() {
+                    out.push_str(text);
+                    out.push('\n');
+                }
+                out.push_str(&fence);
+                block_break(out);
+            }
+
+            //a GitHub-flavoured table, which must start with a header row
+            AST::Table { rows, .. } => {
+                let mut lines: Vec<Vec<String>> = Vec::new();
+                for row in rows {
+                    if let AST::Row { cells, .. } = row {
+                        lines.push(cells.iter().map(|c| self.cell(c)).collect());
+                    }
+                }
+                let columns = lines.iter().map(Vec::len).max().unwrap_or(0);
+                if columns == 0 {
+                    return;
+                }
+                if !matches!(rows.first(), Some(AST::Row { header: true, .. })) {
+                    lines.insert(0, Vec::new());
+                }
+                block_break(out);
+                for (i, line) in lines.iter().enumerate() {
+                    out.push('|');
+                    for c in 0..columns {
+                        out.push(' ');
+                        out.push_str(line.get(c).map_or("", String::as_str));
+                        out.push_str(" |");
+                    }
+                    out.push('\n');
+                    if i == 0 {
+                        out.push('|');
+                        out.push_str(&" --- |".repeat(columns));
+                        out.push('\n');
+                    }
+                }
+                block_break(out);
+            }
+            AST::Row { .. } | AST::Cell { .. } => {}
+
+            //every line of the quote, its attribution and any nested quote included,
+            //is marked with `>`
+            AST::Quote { items, cite, .. } => {
+                let mut inner = String::new();
+                for it in items {
+                    self.render(it, &mut inner);
+                }
+                let cite = self.inline(cite);
+                if !cite.is_empty() {
+                    block_break(&mut inner);
+                    inner.push_str("— ");
+                    inner.push_str(&cite);
+                }
+                block_break(out);
+                for line in tidy(&inner).lines() {
+                    out.push('>');
+                    if !line.is_empty() {
+                        out.push(' ');
+                        out.push_str(line);
+                    }
+                    out.push('\n');
+                }
+                block_break(out);
+            }
+            AST::Rule(_) => {
+                block_break(out);
+                out.push_str("---");
+                block_break(out);
+            }
+
+            AST::Bold(items, _) => {
+                let text = self.inline(items);
+                emphasis(out, "**", &text);
+            }
+            //`_` does not work inside a word, as in "word#GIMMEH ITALICS it#MKAY."
+            AST::Italics(items, _) => {
+                let text = self.inline(items);
+                emphasis(out, "*", &text);
+            }
+            AST::Audio(url, _) => link(out, "audio", url),
+            AST::Video(url, _) => link(out, "video", url),
+            AST::Link { url, items, .. } => {
+                let text = self.inline(items);
+                if text.is_empty() {
+                    link(out, &escape_text(url, false), url);
+                } else {
+                    link(out, &text, url);
+                }
+            }
+            AST::Image { url, alt, .. } => {
+                out.push('!');
+                link(out, &escape_text(alt, false), url);
+            }
+            AST::Newline(_) => {
+                trim_trailing_spaces(out);
+                out.push_str("\\\n");
+                out.push_str(&self.indent());
+            }
+
+            //a line break in the source could start a list or heading
+            AST::Text(t, _) => out.push_str(&escape_text(&collapse_spaces(t), at_line_start(out))),
+
+            AST::VarDefine { .. } => {}
+
+            AST::VarUse { value, .. } => {
+                if let Some(v) = value {
+                    out.push_str(&escape_text(v, at_line_start(out)));
+                }
+            }
+        }
+    }
+
+    //inline content rendered on its own, without surrounding spaces
+    fn inline(&mut self, items: &[AST]) -> String {
+        let mut text = String::new();
+        for it in items {
+            self.render(it, &mut text);
+        }
+        text.trim().to_string()
+    }
+
+    //the content of a table cell, which must fit on one line
+    fn cell(&mut self, cell: &AST) -> String {
+        let mut text = String::new();
+        if let AST::Cell { items, .. } = cell {
+            for it in items {
+                self.render(it, &mut text);
+            }
+        }
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    //continuation lines of a list item line up with the text after its bullet
+    fn indent(&self) -> String {
+        " ".repeat(self.markers.iter().sum())
+    }
+}
+
+impl Backend for MarkdownBackend {
+    fn emit(&mut self, program: &AST, out: &mut dyn Write) -> io::Result<()> {
+        let mut md = String::new();
+        self.render(program, &mut md);
+        out.write_all(tidy(&md).as_bytes())
+    }
+}
+
+//`txt` is already Markdown
+fn emphasis(out: &mut String, marker: &str, txt: &str) {
+    if txt.is_empty() {
+        return;
+    }
+    out.push_str(marker);
+    out.push_str(txt);
+    out.push_str(marker);
+}
+
+fn link(out: &mut String, label: &str, url: &str) {
+    out.push('[');
+    out.push_str(label);
+    out.push_str("](");
+    out.push_str(&escape_url(url));
+    out.push(')');
+}
+
+/// Whether text written next would open a block: nothing but indentation or
+/// a list marker precedes it on its line.
+fn at_line_start(out: &str) -> bool {
+    let line = out.rsplit('\n').next().unwrap_or("").trim();
+    line.is_empty()
+        || line == "-"
+        || line
+            .strip_suffix('.')
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Ends the current line, unless one was just ended.
+fn line_break(out: &mut String) {
+    trim_trailing_spaces(out);
+    //a hard break at the end of a block would show as a literal backslash
+    if out.ends_with("\\\n") {
+        out.truncate(out.len() - 2);
+    }
+    backend::line_break(out);
+}
+
+/// Leaves a blank line, which separates Markdown blocks.
+fn block_break(out: &mut String) {
+    line_break(out);
+    backend::block_break(out);
+}
+
+/// Strips trailing spaces from every line and runs of blank lines, and ends
+/// the document with exactly one newline. Fenced code is left as it is.
+fn tidy(md: &str) -> String {
+    let mut out = String::with_capacity(md.len());
+    let mut blank = true;
+    let mut fence: Option<&str> = None;
+    for line in md.lines() {
+        if let Some(f) = fence {
+            out.push_str(line);
+            out.push('\n');
+            if line == f {
+                fence = None;
+            }
+            continue;
+        }
+        if line.starts_with("```") {
+            fence = Some(&line[..line.len() - line.trim_start_matches('`').len()]);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            if !blank {
+                out.push('\n');
+            }
+            blank = true;
+        } else {
+            out.push_str(line);
+            out.push('\n');
+            blank = false;
+        }
+    }
+    while out.ends_with("\n\n") {
+        out.pop();
+    }
+    out
+}
+
+/// Backslash-escapes characters Markdown would read as syntax. At the start
+/// of a line, markers that would begin a list, heading or thematic break are
+/// escaped as well.
+fn escape_text(s: &str, line_start: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '!' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '&' => out.push_str("&amp;"),
+            _ => out.push(c),
+        }
+    }
+    if line_start {
+        let first = out.chars().next();
+        if matches!(first, Some('-' | '+' | '=')) {
+            out.insert(0, '\\');
+        } else if let Some(digits) = out.find(|c: char| !c.is_ascii_digit()) {
+            //"1." or "1)" would start an ordered list
+            if digits > 0 && matches!(out[digits..].chars().next(), Some('.' | ')')) {
+                out.insert(digits, '\\');
+            }
+        }
+    }
+    out
+}
+
+/// URLs with spaces or parentheses are wrapped in angle brackets, the
+/// CommonMark form that allows them.
+fn escape_url(url: &str) -> String {
+    if url.contains([' ', '(', ')', '<', '>']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+/// A double-quoted YAML scalar.
+fn yaml_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,468 @@
+use std::io::{self, Write};
+
+use crate::backend::Backend;
+use crate::syntax_analyzer::AST;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    bold: bool,
+    italic: bool,
+}
+
+//one character of inline content; '\n' marks a hard line break
+type Cell = (char, Style);
+
+/// Renders the tree as plain text for reading in a terminal: paragraphs are
+/// word-wrapped to `width` columns and, when `color` is set, bold and italic
+/// text is shown with ANSI escapes.
+#[derive(Debug, Clone)]
+pub struct TextBackend {
+    width: usize,
+    color: bool,
+}
+
+impl TextBackend {
+    pub fn new(width: usize, color: bool) -> Self {
+        TextBackend {
+            width: width.max(1),
+            color,
+        }
+    }
+
+    //Renders a block-level node, appending one entry per block to `blocks`.
+    fn block(&self, node: &AST, blocks: &mut Vec<String>) {
+        match node {
+            AST::Program { parts, .. } => {
+                //inline content outside a paragraph is gathered into one
+                let mut pending: Vec<Cell> = Vec::new();
+                for p in parts {
+                    match p {
+                        AST::Head { .. }
+                        | AST::Paragraph { .. }
+                        | AST::Heading { .. }
+                        | AST::List { .. }
+                        | AST::Table { .. }
+                        | AST::CodeBlock { .. }
+                        | AST::Quote { .. }
+                        | AST::Rule(_)
+                        | AST::Audio(..)
+                        | AST::Video(..) => {
+                            self.flush_paragraph(&mut pending, blocks);
+                            self.block(p, blocks);
+                        }
+                        _ => self.inline(p, Style::default(), &mut pending),
+                    }
+                }
+                self.flush_paragraph(&mut pending, blocks);
+            }
+
+            AST::Head { title, author, .. } => {
+                let title = title.trim();
+                let mut lines = Vec::new();
+                if !title.is_empty() {
+                    let rule = "=".repeat(title.chars().count());
+                    if self.color {
+                        lines.push(format!("{}{}{}", BOLD, title, RESET));
+                    } else {
+                        lines.push(title.to_string());
+                    }
+                    lines.push(rule);
+                }
+                if let Some(author) = author.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+                    lines.push(format!("by {}", author));
+                }
+                if !lines.is_empty() {
+                    blocks.push(lines.join("\n"));
+                }
+            }
+
+            AST::Paragraph { items, .. } => self.flow(items, blocks),
+
+            //the two top levels are underlined like the title; the rest are marked
+            //with a `#` per level, as bold alone is lost without colour
+            AST::Heading { level, items, .. } => {
+                let mut cells = Vec::new();
+                for it in items {
+                    self.inline(it, Style { bold: true, italic: false }, &mut cells);
+                }
+                let marker = match level {
+                    1 | 2 => String::new(),
+                    _ => format!("{} ", "#".repeat((*level as usize).min(6))),
+                };
+                let mut lines = Vec::new();
+                self.wrap(&cells, &marker, &" ".repeat(marker.len()), &mut lines);
+                let rule = match level {
+                    1 => '=',
+                    2 => '-',
+                    _ => {
+                        blocks.push(lines.join("\n"));
+                        return;
+                    }
+                };
+                let width = lines.iter().map(|l| visible_width(l)).max().unwrap_or(0);
+                lines.push(rule.to_string().repeat(width));
+                blocks.push(lines.join("\n"));
+            }
+
+            AST::List {
+                ordered,
+                start,
+                items,
+                ..
+            } => {
+                let mut lines = Vec::new();
+                self.list(items, ordered.then(|| start.unwrap_or(1)), "  ", &mut lines);
+                if !lines.is_empty() {
+                    blocks.push(lines.join("\n"));
+                }
+            }
+
+            AST::Audio(..) | AST::Video(..) => {
+                let mut cells = Vec::new();
+                self.inline(node, Style::default(), &mut cells);
+                self.flush_paragraph(&mut cells, blocks);
+            }
+
+            //indented and never wrapped, so the code keeps its shape
+            AST::CodeBlock { text, .. } => {
+                let lines: Vec<String> = text
+                    .lines()
+                    .map(|l| if l.trim().is_empty() { String::new() } else { format!("    {}", l) })
+                    .collect();
+                if !lines.is_empty() {
+                    blocks.push(lines.join("\n"));
+                }
+            }
+
+            //columns are padded to line up and the leading header rows are ruled off
+            AST::Table { rows, .. } => {
+                let mut grid: Vec<Vec<String>> = Vec::new();
+                let mut header_rows = 0;
+                for row in rows {
+                    if let AST::Row { header, cells, .. } = row {
+                        if *header && header_rows == grid.len() {
+                            header_rows += 1;
+                        }
+                        grid.push(cells.iter().map(|c| self.cell(c, *header)).collect());
+                    }
+                }
+                let columns = grid.iter().map(Vec::len).max().unwrap_or(0);
+                if columns == 0 {
+                    return;
+                }
+                let mut widths = vec![0; columns];
+                for line in &grid {
+                    for (c, text) in line.iter().enumerate() {
+                        widths[c] = widths[c].max(visible_width(text));
+                    }
+                }
+                let mut lines = Vec::new();
+                for (i, line) in grid.iter().enumerate() {
+                    if i == header_rows && i > 0 {
+                        let rules: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+                        lines.push(rules.join("-+-"));
+                    }
+                    let padded: Vec<String> = (0..columns)
+                        .map(|c| {
+                            let text = line.get(c).map_or("", String::as_str);
+                            format!("{}{}", text, " ".repeat(widths[c] - visible_width(text)))
+                        })
+                        .collect();
+                    lines.push(padded.join(" | ").trim_end().to_string());
+                }
+                blocks.push(lines.join("\n"));
+            }
+
+            //marked off by `> ` on every line, with the attribution last; the
+            //quote is wrapped narrower to leave room for the marks
+            AST::Quote { items, cite, .. } => {
+                let inner = TextBackend::new(self.width.saturating_sub(2), self.color);
+                let mut quoted = Vec::new();
+                inner.flow(items, &mut quoted);
+                let mut cells = Vec::new();
+                for it in cite {
+                    self.inline(it, Style::default(), &mut cells);
+                }
+                if has_text(&cells) {
+                    let mut lines = Vec::new();
+                    inner.wrap(&cells, "— ", "  ", &mut lines);
+                    quoted.push(lines.join("\n"));
+                }
+                let lines: Vec<String> = quoted
+                    .join("\n\n")
+                    .lines()
+                    .map(|l| if l.is_empty() { ">".to_string() } else { format!("> {}", l) })
+                    .collect();
+                if !lines.is_empty() {
+                    blocks.push(lines.join("\n"));
+                }
+            }
+            AST::Rule(_) => blocks.push("-".repeat(self.width)),
+
+            AST::Comment(..)
+            | AST::Text(..)
+            | AST::Bold(..)
+            | AST::Italics(..)
+            | AST::Link { .. }
+            | AST::Image { .. }
+            | AST::ListItem { .. }
+            | AST::Row { .. }
+            | AST::Cell { .. }
+            | AST::Newline(_)
+            | AST::VarDefine { .. }
+            | AST::VarUse { .. } => {}
+        }
+    }
+
+    //Inline content is wrapped into paragraphs around the blocks among it.
+    fn flow(&self, items: &[AST], blocks: &mut Vec<String>) {
+        let mut cells: Vec<Cell> = Vec::new();
+        for it in items {
+            if let AST::Paragraph { .. }
+            | AST::List { .. }
+            | AST::Heading { .. }
+            | AST::Table { .. }
+            | AST::CodeBlock { .. }
+            | AST::Quote { .. }
+            | AST::Rule(_) = it
+            {
+                self.flush_paragraph(&mut cells, blocks);
+                self.block(it, blocks);
+            } else {
+                self.inline(it, Style::default(), &mut cells);
+            }
+        }
+        self.flush_paragraph(&mut cells, blocks);
+    }
+
+    //Items are bulleted, or numbered counting from `number`, after `indent`.
+    //Wrapped lines hang under the text rather than the marker, and so do
+    //nested lists.
+    fn list(&self, items: &[AST], mut number: Option<u32>, indent: &str, lines: &mut Vec<String>) {
+        for item in items {
+            match item {
+                AST::ListItem { items, .. } => {
+                    let marker = match number.as_mut() {
+                        Some(n) => {
+                            *n += 1;
+                            format!("{}. ", *n - 1)
+                        }
+                        None => "- ".to_string(),
+                    };
+                    let bullet = format!("{}{}", indent, marker);
+                    let hang = format!("{}{}", indent, " ".repeat(marker.len()));
+                    let mut cells = Vec::new();
+                    let mut first = true;
+                    for it in items {
+                        if let AST::List {
+                            ordered,
+                            start,
+                            items: nested,
+                            ..
+                        } = it
+                        {
+                            self.wrap(&cells, if first { &bullet } else { &hang }, &hang, lines);
+                            cells.clear();
+                            first = false;
+                            let number = ordered.then(|| start.unwrap_or(1));
+                            self.list(nested, number, &hang, lines);
+                        } else {
+                            self.inline(it, Style::default(), &mut cells);
+                        }
+                    }
+                    if first || has_text(&cells) {
+                        self.wrap(&cells, if first { &bullet } else { &hang }, &hang, lines);
+                    }
+                }
+                _ => {
+                    let hang = format!("{}  ", indent);
+                    let mut cells = Vec::new();
+                    self.inline(item, Style::default(), &mut cells);
+                    if has_text(&cells) {
+                        self.wrap(&cells, &hang, &hang, lines);
+                    }
+                }
+            }
+        }
+    }
+
+    fn inline(&self, node: &AST, style: Style, cells: &mut Vec<Cell>) {
+        match node {
+            //only NEWLINE breaks a line; the text is wrapped anew
+            AST::Text(t, _) => push_str(cells, &t.replace(char::is_whitespace, " "), style),
+            AST::VarUse { value, .. } => {
+                if let Some(v) = value {
+                    push_str(cells, v, style);
+                }
+            }
+            AST::Bold(items, _) => {
+                for it in items {
+                    self.inline(it, Style { bold: true, ..style }, cells);
+                }
+            }
+            AST::Italics(items, _) => {
+                for it in items {
+                    self.inline(it, Style { italic: true, ..style }, cells);
+                }
+            }
+            AST::Newline(_) => cells.push(('\n', style)),
+            AST::Audio(url, _) => push_str(cells, &format!("[audio: {}]", url.trim()), style),
+            AST::Video(url, _) => push_str(cells, &format!("[video: {}]", url.trim()), style),
+            //the address follows the link text, since it cannot be followed
+            AST::Link { url, items, .. } => {
+                let start = cells.len();
+                for it in items {
+                    self.inline(it, style, cells);
+                }
+                while cells.len() > start && cells.last().is_some_and(|c| c.0 == ' ') {
+                    cells.pop();
+                }
+                if items.is_empty() {
+                    push_str(cells, url.trim(), style);
+                } else {
+                    push_str(cells, &format!(" <{}>", url.trim()), style);
+                }
+            }
+            AST::Image { url, alt, .. } => {
+                let label = if alt.trim().is_empty() { url.trim() } else { alt.trim() };
+                push_str(cells, &format!("[image: {}]", label), style);
+            }
+
+            AST::Program { .. }
+            | AST::Comment(..)
+            | AST::Head { .. }
+            | AST::Paragraph { .. }
+            | AST::Heading { .. }
+            | AST::List { .. }
+            | AST::ListItem { .. }
+            | AST::Table { .. }
+            | AST::Row { .. }
+            | AST::Cell { .. }
+            | AST::CodeBlock { .. }
+            | AST::Quote { .. }
+            | AST::Rule(_)
+            | AST::VarDefine { .. } => {}
+        }
+    }
+
+    //the content of a table cell on one line, bold in a header row
+    fn cell(&self, cell: &AST, header: bool) -> String {
+        let mut cells = Vec::new();
+        if let AST::Cell { items, .. } = cell {
+            let style = Style { bold: header, italic: false };
+            for it in items {
+                self.inline(it, style, &mut cells);
+            }
+        }
+        let words: Vec<String> = cells
+            .split(|c| c.0.is_whitespace())
+            .filter(|w| !w.is_empty())
+            .map(|w| self.styled(w))
+            .collect();
+        words.join(" ")
+    }
+
+    fn flush_paragraph(&self, cells: &mut Vec<Cell>, blocks: &mut Vec<String>) {
+        if has_text(cells) {
+            let mut lines = Vec::new();
+            self.wrap(cells, "", "", &mut lines);
+            blocks.push(lines.join("\n"));
+        }
+        cells.clear();
+    }
+
+    /// Greedily fills lines up to `width` columns. The first line starts with
+    /// `first` and the others with `rest`; a word longer than a line is left
+    /// whole rather than split.
+    fn wrap(&self, cells: &[Cell], first: &str, rest: &str, lines: &mut Vec<String>) {
+        let start = lines.len();
+        let mut prefix = first;
+        for hard in cells.split(|c| c.0 == '\n') {
+            let mut line = prefix.to_string();
+            let mut len = prefix.chars().count();
+            let mut empty = true;
+            for word in hard.split(|c| c.0.is_whitespace()).filter(|w| !w.is_empty()) {
+                if !empty && len + 1 + word.len() > self.width {
+                    lines.push(line.trim_end().to_string());
+                    line = rest.to_string();
+                    len = rest.chars().count();
+                    empty = true;
+                }
+                if !empty {
+                    line.push(' ');
+                    len += 1;
+                }
+                line.push_str(&self.styled(word));
+                len += word.len();
+                empty = false;
+            }
+            lines.push(line.trim_end().to_string());
+            prefix = rest;
+        }
+        //a break at the end of the content leaves nothing to show
+        while lines.len() > start + 1 && lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+    }
+
+    fn styled(&self, word: &[Cell]) -> String {
+        let mut out = String::new();
+        let mut current = Style::default();
+        for &(c, style) in word {
+            if self.color && style != current {
+                if current != Style::default() {
+                    out.push_str(RESET);
+                }
+                if style.bold {
+                    out.push_str(BOLD);
+                }
+                if style.italic {
+                    out.push_str(ITALIC);
+                }
+                current = style;
+            }
+            out.push(c);
+        }
+        if current != Style::default() {
+            out.push_str(RESET);
+        }
+        out
+    }
+}
+
+impl Backend for TextBackend {
+    fn emit(&mut self, program: &AST, out: &mut dyn Write) -> io::Result<()> {
+        let mut blocks = Vec::new();
+        self.block(program, &mut blocks);
+        let mut text = blocks.join("\n\n");
+        text.push('\n');
+        out.write_all(text.as_bytes())
+    }
+}
+
+fn push_str(cells: &mut Vec<Cell>, s: &str, style: Style) {
+    cells.extend(s.chars().map(|c| (c, style)));
+}
+
+//columns a rendered line takes up, not counting ANSI escapes
+fn visible_width(line: &str) -> usize {
+    let mut width = 0;
+    let mut escape = false;
+    for c in line.chars() {
+        match c {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+fn has_text(cells: &[Cell]) -> bool {
+    cells.iter().any(|c| !c.0.is_whitespace())
+}
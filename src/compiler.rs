@@ -1,94 +1,142 @@
 use std::fs::File;
 use std::io::Write;
 
-use crate::lexer::LolLexer;
+use crate::diagnostic::Diagnostic;
+use crate::error::CompileError;
+use crate::lexer::{Lexeme, LolLexer};
 use crate::semantic_analyzer::SemanticAnalyzer;
 use crate::syntax_analyzer::{AST, LolCodeSyntaxAnalyzer, SyntaxAnalyzer};
+use crate::token::Token;
+use crate::Options;
 pub trait Compiler {
-    fn compile(&mut self, source: &str);
-    fn next_token(&mut self) -> String;
-    fn parse(&mut self);
-    fn current_token(&self) -> String;
-    fn set_current_token(&mut self, tok: String);
+    fn compile(&mut self, source: &str) -> Result<(), Vec<Diagnostic>>;
+    fn next_token(&mut self) -> Result<Lexeme, CompileError>;
+    fn parse(&mut self) -> Result<(), Vec<Diagnostic>>;
+    fn current_token(&self) -> Token;
+    fn set_current_token(&mut self, tok: Token);
 }
 pub struct LolCompiler {
     lexer: LolLexer,
-    current: String,
-    tokens: Vec<String>,
+    current: Token,
+    tokens: Vec<Lexeme>,
     parser: Option<LolCodeSyntaxAnalyzer>,
     tree: Vec<AST>,
-    srcFileName: String,
+    src_file_name: String,
+    options: Options,
 }
 
 impl LolCompiler {
-    pub fn new(source: &str, sourceTitle: String) -> Self {
+    pub fn new(source: &str, source_title: String) -> Self {
         Self {
-            lexer: LolLexer::new(&source),
-            current: String::new(),
+            lexer: LolLexer::new(source),
+            current: Token::Eof,
             tokens: Vec::new(),
             parser: None,
             tree: Vec::new(),
-            srcFileName: sourceTitle,
+            src_file_name: source_title,
+            options: Options::default(),
         }
     }
-}
-impl Compiler for LolCompiler {
+
+    /// Sets the output format `build` produces and how it is rendered;
+    /// HTML by default.
+    pub fn with_options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
 
     ///This function creates Syntax Analyzer and Semantics analyzer.
-    /// It calls on the lexer to scan the src. If successful, runs the parser
-    ///then runs the semantics analysis.
-    
-    fn compile(&mut self, source: &str) {
-       
-        let mut tk = self.next_token();
-        while tk != "EOF" {
-            self.tokens.push(tk.clone());
-            tk = self.next_token();
-        }
+    /// It calls on the lexer to scan the src, runs the parser on the tokens it
+    ///could read, then runs the semantics analysis and hands the resolved tree to the target's backend.
+    ///Every problem found along the way is returned instead.
+    pub fn build(&mut self, source: &str) -> Result<String, Vec<Diagnostic>> {
+        // the lexer skips past a bad annotation, so parse what is left to
+        // report syntax and scope errors along with it
+        let (tokens, mut diagnostics) = crate::scan(source);
+        self.tokens = tokens;
 
-        let parser = LolCodeSyntaxAnalyzer {
-            tokens: self.tokens.clone(),
-            position: 0,
-            ast: Vec::new(),
-        };
+        let parser = LolCodeSyntaxAnalyzer::from_tokens(self.tokens.clone());
         self.parser = Some(parser);
-        self.parse();
-    
+        if let Err(d) = self.parse() {
+            diagnostics.extend(d);
+        }
 
         let mut sem_analyzer = SemanticAnalyzer::new();
-        let mut html = String::new();
+        let mut resolved = None;
         if let Some(ast) = self.tree.first() {
-            sem_analyzer.check_program(ast); //The parse tree created using teh Parser is passed for semantics analysis
-            html = sem_analyzer.generate(ast);
+            //The parse tree created using teh Parser is passed for semantics analysis
+            match sem_analyzer.check_program(ast) {
+                Ok(tree) => resolved = Some(tree),
+                Err(d) => diagnostics.extend(d),
+            }
         }
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        let mut out: Vec<u8> = Vec::new();
+        if let Some(tree) = resolved {
+            self.options
+                .target
+                .backend(&self.options)
+                .emit(&tree, &mut out).map_err(|e| {
+                vec![Diagnostic::from(CompileError::Io {
+                    message: format!("failed to generate output: {}", e),
+                })]
+            })?;
+        }
+        String::from_utf8(out).map_err(|e| {
+            vec![Diagnostic::from(CompileError::Io {
+                message: format!("generated output is not UTF-8: {}", e),
+            })]
+        })
+    }
+}
+impl Compiler for LolCompiler {
+    /// Builds `source` and writes the output to the file given to `new`.
+    fn compile(&mut self, source: &str) -> Result<(), Vec<Diagnostic>> {
+        let output = self.build(source)?;
         //Creates and output the HTML file.
-        let mut file: File = File::create(self.srcFileName.clone()).expect("Failed to create file");
+        let mut file: File = File::create(&self.src_file_name).map_err(|e| {
+            vec![Diagnostic::from(CompileError::Io {
+                message: format!("failed to create {}: {}", self.src_file_name, e),
+            })]
+        })?;
 
-        file.write_all(html.as_bytes())
-            .expect("Failed to write to file");
+        file.write_all(output.as_bytes()).map_err(|e| {
+            vec![Diagnostic::from(CompileError::Io {
+                message: format!("failed to write {}: {}", self.src_file_name, e),
+            })]
+        })
     }
 
-    fn next_token(&mut self) -> String {//initates the lexer
-        let token = self.lexer.next_token();
-        self.current = token.clone();
-        return token;
+    fn next_token(&mut self) -> Result<Lexeme, CompileError> {//initates the lexer
+        let lexeme = self.lexer.next_token()?;
+        self.current = lexeme.token.clone();
+        Ok(lexeme)
     }
 
     /// Runs the syntax analyzer starting from <lolcode>.
-    fn parse(&mut self) {
+    fn parse(&mut self) -> Result<(), Vec<Diagnostic>> {
         if let Some(p) = self.parser.as_mut() {
-            p.parse_lolcode(); // exits on syntax error
+            if let Err(e) = p.parse_lolcode() {
+                p.diagnostics.push(e.into());
+            }
             self.tree = p.ast.clone();
+            if !p.diagnostics.is_empty() {
+                return Err(p.diagnostics.clone());
+            }
         }
+        Ok(())
     }
 
     /// Get the current token being processed.
-    fn current_token(&self) -> String {
-        return self.current.clone();
+    fn current_token(&self) -> Token {
+        self.current.clone()
     }
 
     /// Set the current token (typically used internally).
-    fn set_current_token(&mut self, tok: String) {
+    fn set_current_token(&mut self, tok: Token) {
         self.current = tok;
     }
 }
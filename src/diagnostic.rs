@@ -0,0 +1,216 @@
+use std::fmt;
+
+use crate::error::CompileError;
+use crate::json;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// One problem found in a source file. The phases collect these rather than
+/// stopping at the first error, so a single run reports everything it can.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub error: CompileError,
+}
+
+impl Diagnostic {
+    /// Renders the diagnostic in the style of rustc: the message, the file
+    /// location, the offending source line with the span underlined, and any
+    /// help note.
+    ///
+    /// ```text
+    /// error[E0102]: expected `#MKAY`, found `#OIC`
+    ///  --> page.lol:5:1
+    ///   |
+    /// 5 | #OIC
+    ///   | ^^^^
+    ///   |
+    ///   = help: did you forget `#MKAY` to close `#GIMMEH BOLD` opened on line 4?
+    /// ```
+    pub fn render(&self, source: &str, file: &str) -> String {
+        let mut out = format!(
+            "{}[{}]: {}\n",
+            self.severity.as_str(),
+            self.error.code(),
+            self.error.message()
+        );
+        let span = match self.error.span() {
+            Some(span) => span,
+            None => {
+                out.push_str(&format!(" --> {}\n", file));
+                return out;
+            }
+        };
+
+        let line_text = source.lines().nth(span.line.saturating_sub(1)).unwrap_or("");
+        let gutter = " ".repeat(span.line.to_string().len());
+        out.push_str(&format!("{}--> {}:{}:{}\n", gutter, file, span.line, span.col));
+        out.push_str(&format!("{} |\n", gutter));
+        out.push_str(&format!("{} | {}\n", span.line, line_text));
+
+        // keep tabs in the padding so the carets line up with the source
+        let prefix: String = line_text
+            .chars()
+            .take(span.col.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        // a span running onto later lines is underlined to the end of its first line
+        let rest = line_text.chars().count().saturating_sub(span.col.saturating_sub(1));
+        let width = span.len().min(rest).max(1);
+        out.push_str(&format!("{} | {}{}\n", gutter, prefix, "^".repeat(width)));
+
+        if let Some(help) = self.error.help() {
+            out.push_str(&format!("{} |\n", gutter));
+            out.push_str(&format!("{} = help: {}\n", gutter, help));
+        }
+        out
+    }
+
+    /// The diagnostic as a single-line JSON object, for `--error-format=json`.
+    /// Location fields are `null` for errors not tied to the source (I/O).
+    pub fn to_json(&self, file: &str) -> String {
+        let (line, column, length) = match self.error.span() {
+            Some(span) => (
+                span.line.to_string(),
+                span.col.to_string(),
+                span.len().to_string(),
+            ),
+            None => ("null".to_string(), "null".to_string(), "null".to_string()),
+        };
+        format!(
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"span_length\":{},\"help\":{}}}",
+            json::string(self.severity.as_str()),
+            json::string(self.error.code()),
+            json::string(self.error.message()),
+            json::string(file),
+            line,
+            column,
+            length,
+            json::opt_string(self.error.help())
+        )
+    }
+}
+
+impl From<CompileError> for Diagnostic {
+    fn from(error: CompileError) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            error,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+    use crate::{Options, compile_str};
+
+    fn lex_error(span: Span) -> Diagnostic {
+        Diagnostic::from(CompileError::Lex {
+            code: "E0001",
+            span,
+            message: "bad".to_string(),
+        })
+    }
+
+    #[test]
+    fn renders_the_unclosed_bold_example() {
+        let source = "#HAI
+#MAEK HEAD #GIMMEH TITLE t #MKAY #OIC
+#MAEK PARAGRAF
+#GIMMEH BOLD loud
+#OIC
+#KTHXBYE
+";
+        let diagnostics = compile_str(source, &Options::default()).unwrap_err();
+        assert_eq!(
+            diagnostics[0].render(source, "page.lol"),
+            "error[E0102]: expected `#MKAY`, found `#OIC`
+ --> page.lol:5:1
+  |
+5 | #OIC
+  | ^^^^
+  |
+  = help: did you forget `#MKAY` to close `#GIMMEH BOLD` opened on line 4?
+"
+        );
+    }
+
+    #[test]
+    fn underlines_the_span_under_its_source_line() {
+        let source = "#HAI\none #FOO two\n#KTHXBYE\n";
+        let rendered = lex_error(Span::new(9, 13, 2, 5)).render(source, "a.lol");
+        assert_eq!(
+            rendered,
+            "error[E0001]: bad\n --> a.lol:2:5\n  |\n2 | one #FOO two\n  |     ^^^^\n"
+        );
+    }
+
+    #[test]
+    fn gutter_widens_with_the_line_number() {
+        let source = format!("{}x\n", "\n".repeat(11));
+        let rendered = lex_error(Span::new(11, 12, 12, 1)).render(&source, "a.lol");
+        assert_eq!(rendered, "error[E0001]: bad\n  --> a.lol:12:1\n   |\n12 | x\n   | ^\n");
+    }
+
+    #[test]
+    fn caret_padding_keeps_tabs() {
+        let source = "\t\t#FOO\n";
+        let rendered = lex_error(Span::new(2, 6, 1, 3)).render(source, "a.lol");
+        assert!(rendered.ends_with("1 | \t\t#FOO\n  | \t\t^^^^\n"), "{}", rendered);
+
+        let source = "\ta\t#FOO\n";
+        let rendered = lex_error(Span::new(3, 7, 1, 4)).render(source, "a.lol");
+        assert!(rendered.ends_with("  | \t \t^^^^\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn span_over_several_lines_is_underlined_to_the_end_of_the_first() {
+        let source = "#HAI\nab #OBTW never\nclosed\n";
+        let rendered = lex_error(Span::new(8, 26, 2, 4)).render(source, "a.lol");
+        assert!(rendered.ends_with("2 | ab #OBTW never\n  |    ^^^^^^^^^^^\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn empty_span_still_gets_a_caret() {
+        let source = "#HAI\n";
+        let rendered = lex_error(Span::new(5, 5, 2, 1)).render(source, "a.lol");
+        assert!(rendered.ends_with("2 | \n  | ^\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn help_follows_the_snippet_and_io_errors_have_no_snippet() {
+        let error = CompileError::Semantic {
+            code: "E0201",
+            span: Span::new(0, 1, 1, 1),
+            message: "undefined".to_string(),
+            help: Some("define it".to_string()),
+        };
+        let rendered = Diagnostic::from(error).render("x\n", "a.lol");
+        assert!(rendered.ends_with("  | ^\n  |\n  = help: define it\n"), "{}", rendered);
+
+        let error = CompileError::Io {
+            message: "cannot read a.lol".to_string(),
+        };
+        let rendered = Diagnostic::from(error).render("", "a.lol");
+        assert_eq!(rendered, "error[E0301]: cannot read a.lol\n --> a.lol\n");
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt;
+
+use crate::span::Span;
+
+/// Error raised by one of the compiler phases, located by the span of the
+/// offending source text. Syntax and semantic errors may carry a help note
+/// suggesting a fix.
+///
+/// Every error site has a stable code for tools to match on:
+///
+/// | code  | meaning                                             |
+/// |-------|-----------------------------------------------------|
+/// | E0001 | `#` followed by something that is not an annotation |
+/// | E0002 | `#` with no annotation after it                     |
+/// | E0003 | source ended in the middle of a token               |
+/// | E0101 | unexpected token                                    |
+/// | E0102 | construct not closed by its `#MKAY`/`#OIC`/`#TLDR`  |
+/// | E0103 | head annotation given more than once                |
+/// | E0201 | variable used where it is not defined               |
+/// | E0202 | heading level outside 1 to 6                        |
+/// | E0203 | table row with a different number of cells          |
+/// | E0204 | link or media URL whose scheme can run code         |
+/// | E0301 | reading or writing a file failed                    |
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompileError {
+    Lex {
+        code: &'static str,
+        span: Span,
+        message: String,
+    },
+    Syntax {
+        code: &'static str,
+        span: Span,
+        message: String,
+        help: Option<String>,
+    },
+    Semantic {
+        code: &'static str,
+        span: Span,
+        message: String,
+        help: Option<String>,
+    },
+    Io {
+        message: String,
+    },
+}
+
+impl CompileError {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            CompileError::Lex { span, .. }
+            | CompileError::Syntax { span, .. }
+            | CompileError::Semantic { span, .. } => Some(*span),
+            CompileError::Io { .. } => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompileError::Lex { code, .. }
+            | CompileError::Syntax { code, .. }
+            | CompileError::Semantic { code, .. } => code,
+            CompileError::Io { .. } => "E0301",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CompileError::Lex { message, .. }
+            | CompileError::Syntax { message, .. }
+            | CompileError::Semantic { message, .. }
+            | CompileError::Io { message } => message,
+        }
+    }
+
+    pub fn help(&self) -> Option<&str> {
+        match self {
+            CompileError::Syntax { help, .. } | CompileError::Semantic { help, .. } => {
+                help.as_deref()
+            }
+            CompileError::Lex { .. } | CompileError::Io { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (phase, span, message) = match self {
+            CompileError::Lex { span, message, .. } => ("Lexical", span, message),
+            CompileError::Syntax { span, message, .. } => ("Syntax", span, message),
+            CompileError::Semantic { span, message, .. } => ("Semantic", span, message),
+            CompileError::Io { message } => return write!(f, "I/O error: {}", message),
+        };
+        write!(
+            f,
+            "{} error at line {}, col {}: {}",
+            phase, span.line, span.col, message
+        )
+    }
+}
+
+impl std::error::Error for CompileError {}
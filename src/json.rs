@@ -0,0 +1,28 @@
+//! Just enough JSON writing for the machine-readable output modes.
+
+/// `s` as a JSON string literal, quotes included.
+pub fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// An optional string as a JSON string or `null`.
+pub fn opt_string(s: Option<&str>) -> String {
+    match s {
+        Some(s) => string(s),
+        None => "null".to_string(),
+    }
+}
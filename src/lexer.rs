@@ -1,18 +1,64 @@
+use crate::error::CompileError;
+use crate::json;
+use crate::span::Span;
+use crate::token::{Annotation, Token};
+
 pub trait LexicalAnalyzer {
-    fn get_char(&mut self) -> char;
+    fn get_char(&mut self) -> Result<char, CompileError>;
     fn add_char(&mut self, c: char);
     fn lookup(&self, s: &str) -> bool;
 }
 
+/// A token together with where it was found in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lexeme {
+    pub token: Token,
+    pub span: Span,
+    /// The whitespace between the previous token and this one, as written.
+    pub space: String,
+}
+
+impl Lexeme {
+    /// The token as a JSON object for `--emit tokens-json`. `text` is the
+    /// source spelling, or `null` for the end of file.
+    pub fn to_json(&self) -> String {
+        let text = match &self.token {
+            Token::Eof => None,
+            tok => Some(tok.to_string()),
+        };
+        format!(
+            "{{\"kind\":{},\"text\":{},\"span\":{}}}",
+            json::string(self.token.kind()),
+            json::opt_string(text.as_deref()),
+            self.span.to_json()
+        )
+    }
+}
+
+//how far the lexer is into a `#MAEK KODE` block, whose contents are kept as
+//written rather than split into words
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verbatim {
+    Off,
+    Language,
+    Code,
+}
+
 pub struct LolLexer {
     input: Vec<char>,
     index: usize,
     pub line: usize,
     pub col: usize,
     potential_token: String,
+    // plain annotation words are only keywords straight after #MAEK or #GIMMEH,
+    // so "bold" or "list" in running text stays text
+    expect_annotation: bool,
+    //only `#MAEK KODE` starts a code block; `#GIMMEH KODE` is not one
+    after_maek: bool,
+    verbatim: Verbatim,
 
-    hash_annotations: [&'static str; 11],
-    plain_annotations: [&'static str; 10],
+    hash_annotations: [Token; 11],
+    plain_annotations: [Annotation; 28],
 }
 
 impl LolLexer {
@@ -23,61 +69,119 @@ impl LolLexer {
             line: 1,
             col: 0,
             potential_token: String::new(),
+            expect_annotation: false,
+            after_maek: false,
+            verbatim: Verbatim::Off,
 
             hash_annotations: [
-                "#HAI",
-                "#KTHXBYE",
-                "#OBTW",
-                "#TLDR",
-                "#MAEK",
-                "#OIC",
-                "#GIMMEH",
-                "#MKAY",
-                "#I HAZ",
-                "#IT IZ",
-                "#LEMME SEE",
+                Token::Hai,
+                Token::KthxBye,
+                Token::Obtw,
+                Token::Tldr,
+                Token::Maek,
+                Token::Oic,
+                Token::Gimmeh,
+                Token::Mkay,
+                Token::IHaz,
+                Token::ItIz,
+                Token::LemmeSee,
             ],
             plain_annotations: [
-                "HEAD", "TITLE", "PARAGRAF", "BOLD", "ITALICS", "LIST", "ITEM", "NEWLINE",
-                "SOUNDZ", "VIDZ",
+                Annotation::Head,
+                Annotation::Title,
+                Annotation::Paragraf,
+                Annotation::Bold,
+                Annotation::Italics,
+                Annotation::List,
+                Annotation::Item,
+                Annotation::Newline,
+                Annotation::Soundz,
+                Annotation::Vidz,
+                Annotation::Style,
+                Annotation::Author,
+                Annotation::Description,
+                Annotation::Keywords,
+                Annotation::Language,
+                Annotation::Favicon,
+                Annotation::Heading,
+                Annotation::Link,
+                Annotation::Pikchur,
+                Annotation::Numbrd,
+                Annotation::Tabl,
+                Annotation::Row,
+                Annotation::Cell,
+                Annotation::Hedr,
+                Annotation::Kode,
+                Annotation::Kwote,
+                Annotation::Sez,
+                Annotation::Line,
             ],
         }
     }
 
     //function to return the next valid token
-    pub fn next_token(&mut self) -> String {
-        self.skip_ws();
-        match self.peek() {
+    pub fn next_token(&mut self) -> Result<Lexeme, CompileError> {
+        if self.verbatim != Verbatim::Off {
+            return Ok(self.read_verbatim());
+        }
+        let space = self.skip_ws();
+        let start = self.mark();
+        let token = match self.peek() {
             //pattern matching to handle tokens that start with # and everything else
-            None => "EOF".to_string(),
+            None => Token::Eof,
 
-            Some('#') => self.read_hash_keyword(),
+            Some('#') => self.read_hash_keyword()?,
 
             Some(_) => {
-                let w = self.get_token();
+                let w = self.get_token()?;
                 let up = w.to_ascii_uppercase();
-                if self.plain_annotations.contains(&up.as_str()) {
-                    return up;
-                } else {
-                    return format!("TEXT({})", w); //Regular text will be identified using TEXT() wrapper.
+                match self.plain_annotations.iter().find(|a| a.as_str() == up) {
+                    Some(a) if self.expect_annotation => Token::Keyword(*a),
+                    _ => Token::Text(w), //anything that is not an annotation is regular text
                 }
             }
+        };
+        //everything after KODE up to its #OIC is code, not tokens
+        if self.after_maek && token == Token::Keyword(Annotation::Kode) {
+            self.verbatim = Verbatim::Language;
         }
+        self.after_maek = token == Token::Maek;
+        //a modifier such as NUMBRD is followed by the annotation it modifies
+        self.expect_annotation = matches!(
+            token,
+            Token::Maek
+                | Token::Gimmeh
+                | Token::Keyword(Annotation::Numbrd | Annotation::Hedr)
+        );
+        Ok(Lexeme {
+            token,
+            span: self.span_from(start),
+            space,
+        })
+    }
+
+    //the position a token starts at, (index, line, 1-based col)
+    fn mark(&self) -> (usize, usize, usize) {
+        (self.index, self.line, self.col + 1)
+    }
+
+    fn span_from(&self, (start, line, col): (usize, usize, usize)) -> Span {
+        Span::new(start, self.index, line, col)
     }
 
     //return next char
     fn peek(&self) -> Option<char> {
         if self.index < self.input.len() {
-            return Some(self.input[self.index]);
+            Some(self.input[self.index])
         } else {
-            return None;
+            None
         }
     }
 
     fn advance(&mut self) -> Option<char> {
         let c = self.peek();
 
-        if c != None {
+        if c.is_some() {
             self.index += 1;
             if c == Some('\n') {
                 self.line += 1;
@@ -86,37 +190,55 @@ impl LolLexer {
                 self.col += 1;
             }
         }
-        return c;
+        c
     }
 
-    //a helper function to skip white spaces between tokens
-    fn skip_ws(&mut self) {
-        let c = self.peek();
+    //a helper function to skip white spaces between tokens, returning them
+    fn skip_ws(&mut self) -> String {
+        let mut space = String::new();
         while let Some(c) = self.peek() {
             if c.is_whitespace() {
+                space.push(c);
                 self.advance();
             } else {
                 break;
             }
         }
+        space
     }
 
     //returns the next token
-    fn get_token(&mut self) -> String {
+    fn get_token(&mut self) -> Result<String, CompileError> {
         self.potential_token.clear();
         while let Some(c) = self.peek() {
             if c.is_whitespace() || c == '#' {
                 break;
             }
-            let ch = self.get_char();
+            let ch = self.get_char()?;
+            self.add_char(ch);
+        }
+        Ok(self.potential_token.clone())
+    }
+
+    //the word of an annotation after `#`, which ends at anything but a letter
+    //or digit so that punctuation may follow it directly, as in `#MKAY,`
+    fn get_annotation(&mut self) -> Result<String, CompileError> {
+        self.potential_token.clear();
+        while let Some(c) = self.peek() {
+            if !c.is_alphanumeric() {
+                break;
+            }
+            let ch = self.get_char()?;
             self.add_char(ch);
         }
-        self.potential_token.clone()
+        Ok(self.potential_token.clone())
     }
+
     //This function checks that any annotations that start with hash are valid
-    fn read_hash_keyword(&mut self) -> String {
+    fn read_hash_keyword(&mut self) -> Result<Token, CompileError> {
+        let start = self.mark();
         self.advance();
-        let first_token = self.get_token().to_ascii_uppercase();
+        let first_token = self.get_annotation()?.to_ascii_uppercase();
 
         // some tokens such as "#I HAZ" have two parts, so we need to check for them
         let save_i = self.index;
@@ -124,15 +246,15 @@ impl LolLexer {
         let save_col = self.col;
 
         if first_token.is_empty() {
-            self.error("Expected keyword after '#'");
+            return Err(self.error(start, "E0002", "expected an annotation after `#`"));
         }
 
         self.skip_ws();
 
-        let second_token = self.get_token().to_ascii_uppercase();
-        let potentional_FT = format!("#{} {}", first_token, second_token);
-        let complete_token = if self.lookup(&potentional_FT) {
-            potentional_FT
+        let second_token = self.get_annotation()?.to_ascii_uppercase();
+        let potential_two_part = format!("#{} {}", first_token, second_token);
+        let complete_token = if self.lookup(&potential_two_part) {
+            potential_two_part
         } else {
             format!("#{}", first_token)
         };
@@ -143,33 +265,105 @@ impl LolLexer {
             self.col = save_col;
         }
 
-        if !self.lookup(&complete_token) {
-            self.error(&format!(
-                "'{}' is Not a valid token ",
-                complete_token
-            ));
+        match self.hash_keyword(&complete_token) {
+            Some(tok) => Ok(tok),
+            None => Err(self.error(
+                start,
+                "E0001",
+                &format!("`{}` is not a valid annotation", complete_token),
+            )),
         }
+    }
 
-        return complete_token;
+    //Inside `#MAEK KODE`: first the language, a word on the same line, then
+    //everything up to `#OIC` as one token. The line break after `#MAEK KODE`
+    //and the indentation before `#OIC` are not part of the code.
+    fn read_verbatim(&mut self) -> Lexeme {
+        let mut space = String::new();
+        while let Some(c @ (' ' | '\t')) = self.peek() {
+            space.push(c);
+            self.advance();
+        }
+        if self.verbatim == Verbatim::Language {
+            self.verbatim = Verbatim::Code;
+            if self.peek().is_some_and(|c| !c.is_whitespace()) && !self.at_oic() {
+                let start = self.mark();
+                let mut lang = String::new();
+                while let Some(c) = self.peek() {
+                    if c.is_whitespace() || c == '#' {
+                        break;
+                    }
+                    lang.push(c);
+                    self.advance();
+                }
+                return Lexeme {
+                    token: Token::Text(lang),
+                    span: self.span_from(start),
+                    space,
+                };
+            }
+        }
+        self.verbatim = Verbatim::Off;
+        if self.peek() == Some('\r') {
+            self.advance();
+        }
+        if self.peek() == Some('\n') {
+            self.advance();
+        }
+
+        let start = self.mark();
+        let mut code = String::new();
+        while let Some(c) = self.peek() {
+            if self.at_oic() {
+                break;
+            }
+            code.push(c);
+            self.advance();
+        }
+        let span = self.span_from(start);
+        let code = code.trim_end_matches([' ', '\t']);
+        let code = code.strip_suffix('\n').unwrap_or(code);
+        let code = code.strip_suffix('\r').unwrap_or(code);
+        Lexeme {
+            token: Token::Verbatim(code.to_string()),
+            span,
+            space: String::new(),
+        }
     }
 
-    fn error(&self, msg: &str) {
-        eprintln!(
-            "Lexical error at line {}, col {}: {}",
-            self.line, self.col, msg
-        );
-        std::process::exit(1);
+    //whether `#OIC`, which ends a code block, starts here
+    fn at_oic(&self) -> bool {
+        let rest = &self.input[self.index..];
+        rest.len() >= 4
+            && rest[..4].iter().collect::<String>().eq_ignore_ascii_case("#OIC")
+            && rest.get(4).is_none_or(|c| c.is_whitespace() || *c == '#')
+    }
+
+    //maps the spelling of a hash annotation to its token
+    fn hash_keyword(&self, s: &str) -> Option<Token> {
+        let up = s.to_ascii_uppercase();
+        self.hash_annotations
+            .iter()
+            .find(|t| t.to_string() == up)
+            .cloned()
+    }
+
+    fn error(&self, start: (usize, usize, usize), code: &'static str, msg: &str) -> CompileError {
+        CompileError::Lex {
+            code,
+            span: self.span_from(start),
+            message: msg.to_string(),
+        }
     }
 }
 
 //implementation of trait functions
 impl LexicalAnalyzer for LolLexer {
-    fn get_char(&mut self) -> char {
+    fn get_char(&mut self) -> Result<char, CompileError> {
         if let Some(c) = self.advance() {
-            c
+            Ok(c)
         } else {
-            print!("Unexpected EOF");
-            std::process::exit(1);
+            Err(self.error(self.mark(), "E0003", "unexpected end of file"))
         }
     }
     fn add_char(&mut self, c: char) {
@@ -177,14 +371,6 @@ impl LexicalAnalyzer for LolLexer {
     }
 
     fn lookup(&self, s: &str) -> bool {
-        let up = s.to_ascii_uppercase();
-        let mut i = 0;
-        while i < self.hash_annotations.len() {
-            if self.hash_annotations[i] == up {
-                return true;
-            }
-            i += 1;
-        }
-        false
+        self.hash_keyword(s).is_some()
     }
 }
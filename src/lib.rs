@@ -0,0 +1,128 @@
+//! A compiler from LOLCODE markup to HTML and other document formats.
+//!
+//! [`compile_str`] runs the whole pipeline. The phases are also available on
+//! their own: [`tokenize`] scans source into tokens, [`parse`] builds the
+//! [`AST`], and [`analyze`] runs the static scope checks on it, producing the
+//! resolved tree that a [`Backend`] renders. Every phase reports problems as a
+//! list of [`Diagnostic`]s.
+
+use std::path::PathBuf;
+
+pub mod backend;
+pub mod compiler;
+pub mod diagnostic;
+pub mod error;
+mod json;
+pub mod lexer;
+pub mod semantic_analyzer;
+pub mod span;
+pub mod syntax_analyzer;
+pub mod token;
+
+pub use crate::backend::{Backend, HtmlStyle, Target, Theme};
+pub use crate::diagnostic::{Diagnostic, Severity};
+pub use crate::error::CompileError;
+pub use crate::lexer::Lexeme;
+pub use crate::span::Span;
+pub use crate::syntax_analyzer::AST;
+pub use crate::token::{Annotation, Token};
+
+use crate::compiler::LolCompiler;
+use crate::lexer::LolLexer;
+use crate::semantic_analyzer::SemanticAnalyzer;
+use crate::syntax_analyzer::{LolCodeSyntaxAnalyzer, SyntaxAnalyzer};
+
+/// Settings for [`compile_str`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Output format; HTML unless set.
+    pub target: Target,
+    /// Layout of the markup for `Target::Html`.
+    pub html_style: HtmlStyle,
+    /// Built-in stylesheet for `Target::Html`, applied before any other.
+    pub theme: Option<Theme>,
+    /// Extra stylesheets for `Target::Html`, after those the source names.
+    pub css: Vec<String>,
+    /// Copy local stylesheets into the page instead of linking them.
+    pub inline_css: bool,
+    /// Directory of the source file, which `#GIMMEH STYLE` paths are
+    /// relative to, and must stay inside, when inlined.
+    pub source_dir: Option<PathBuf>,
+    /// Column to wrap at for `Target::Text`.
+    pub width: usize,
+    /// Whether `Target::Text` styles bold and italic text with ANSI escapes.
+    pub color: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            target: Target::default(),
+            html_style: HtmlStyle::default(),
+            theme: None,
+            css: Vec::new(),
+            inline_css: false,
+            source_dir: None,
+            width: 80,
+            color: false,
+        }
+    }
+}
+
+/// Compiles LOLCODE source to a document in the format `options.target` names.
+pub fn compile_str(source: &str, options: &Options) -> Result<String, Vec<Diagnostic>> {
+    LolCompiler::new(source, String::new())
+        .with_options(options.clone())
+        .build(source)
+}
+
+/// Scans `source` into tokens. The last token is always `Token::Eof`.
+pub fn tokenize(source: &str) -> Result<Vec<Lexeme>, Vec<Diagnostic>> {
+    let (tokens, diagnostics) = scan(source);
+    if diagnostics.is_empty() {
+        Ok(tokens)
+    } else {
+        Err(diagnostics)
+    }
+}
+
+/// Scans `source` like [`tokenize`], but skips past any bad token instead of
+/// failing, returning the tokens around it along with a diagnostic for each
+/// one skipped. [`parse`] accepts the tokens, so one run can report lexical
+/// and syntax errors together.
+pub fn scan(source: &str) -> (Vec<Lexeme>, Vec<Diagnostic>) {
+    let mut lexer = LolLexer::new(source);
+    let mut tokens = Vec::new();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    loop {
+        match lexer.next_token() {
+            Ok(tk) => {
+                let done = tk.token == Token::Eof;
+                tokens.push(tk);
+                if done {
+                    break;
+                }
+            }
+            Err(e) => diagnostics.push(e.into()),
+        }
+    }
+    (tokens, diagnostics)
+}
+
+/// Parses a token stream from [`tokenize`] into an `AST::Program`.
+pub fn parse(tokens: &[Lexeme]) -> Result<AST, Vec<Diagnostic>> {
+    let mut parser = LolCodeSyntaxAnalyzer::from_tokens(tokens.to_vec());
+    if let Err(e) = parser.parse_lolcode() {
+        parser.diagnostics.push(e.into());
+    }
+    match parser.ast.pop() {
+        Some(program) if parser.diagnostics.is_empty() => Ok(program),
+        _ => Err(parser.diagnostics),
+    }
+}
+
+/// Checks that every variable is defined where it is used, returning the
+/// tree with each `AST::VarUse` resolved to its value.
+pub fn analyze(program: &AST) -> Result<AST, Vec<Diagnostic>> {
+    SemanticAnalyzer::new().check_program(program)
+}
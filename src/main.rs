@@ -1,43 +1,194 @@
-mod compiler;
-mod lexer;
-mod semantic_analyzer;
-mod syntax_analyzer;
-use crate::compiler::{Compiler, LolCompiler};
+use lolcode_compiler::{
+    CompileError, Diagnostic, Lexeme, Options, Target, compile_str, parse, scan,
+};
 use std::env;
 use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::Path;
 use std::process;
-use syntax_analyzer::{LolCodeSyntaxAnalyzer, SyntaxAnalyzer};
 
 fn main() {
     //Checking the file input
     let args: Vec<String> = env::args().collect();
-    if args.len() < 2 {
-        eprintln!("Please Use: lolcompiler.exe  <filename>");
+    let mut json_errors = false;
+    let mut options = Options::default();
+    let mut emit: Option<String> = None;
+    let mut files: Vec<&String> = Vec::new();
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--error-format=json" => json_errors = true,
+            "--error-format=human" => json_errors = false,
+            a if a == "--target" || a.starts_with("--target=") => {
+                options.target = parse_target(&option_value(a, "--target", &mut rest));
+            }
+            a if a == "--emit" || a.starts_with("--emit=") => {
+                let kind = option_value(a, "--emit", &mut rest);
+                if kind != "ast-json" && kind != "tokens-json" {
+                    eprintln!("unknown --emit `{}`, expected `ast-json` or `tokens-json`", kind);
+                    process::exit(1);
+                }
+                emit = Some(kind);
+            }
+            a if a == "--html-style" || a.starts_with("--html-style=") => {
+                let style = option_value(a, "--html-style", &mut rest);
+                options.html_style = style.parse().unwrap_or_else(|e: String| {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                });
+            }
+            a if a == "--theme" || a.starts_with("--theme=") => {
+                let theme = option_value(a, "--theme", &mut rest);
+                options.theme = Some(theme.parse().unwrap_or_else(|e: String| {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }));
+            }
+            a if a == "--css" || a.starts_with("--css=") => {
+                options.css.push(option_value(a, "--css", &mut rest));
+            }
+            "--inline-css" => options.inline_css = true,
+            a if a == "--width" || a.starts_with("--width=") => {
+                options.width = parse_width(&option_value(a, "--width", &mut rest));
+            }
+            a if a.starts_with("--") => {
+                eprintln!("Unknown option {}", a);
+                process::exit(1);
+            }
+            _ => files.push(arg),
+        }
+    }
+    if files.len() != 1 {
+        eprintln!(
+            "Please Use: lolcompiler.exe [--error-format=human|json] [--target html|markdown|text|latex] [--html-style plain|pretty|minified] [--theme NAME] [--css FILE]... [--inline-css] [--width N] [--emit ast-json|tokens-json] <filename>"
+        );
         process::exit(1);
     }
-    let filename = &args[1];
+    let filename = files[0];
     if !filename.ends_with(".lol") {
         eprintln!("The file must use a lol extension(ie filename.lol");
         process::exit(1);
     }
 
-    let outputFileName = format!("{}.html", filename.trim_end_matches(".lol"));
-    println!("{}", outputFileName);
-    let contents = fs::read_to_string(filename).expect("Something went wrong reading the file");
-    
+    //the JSON dumps go to stdout for other tools to read
+    if let Some(kind) = emit {
+        let contents = read_source(filename, json_errors);
+        let (tokens, mut diagnostics) = scan(&contents);
+        if kind == "tokens-json" {
+            if !diagnostics.is_empty() {
+                report(&diagnostics, json_errors, &contents, filename);
+            }
+            let items: Vec<String> = tokens.iter().map(Lexeme::to_json).collect();
+            println!("[{}]", items.join(","));
+        } else {
+            match parse(&tokens) {
+                Ok(program) if diagnostics.is_empty() => println!("{}", program.to_json()),
+                Ok(_) => report(&diagnostics, json_errors, &contents, filename),
+                Err(d) => {
+                    diagnostics.extend(d);
+                    report(&diagnostics, json_errors, &contents, filename);
+                }
+            }
+        }
+        return;
+    }
+
+    let contents = read_source(filename, json_errors);
+    let output_file_name = format!(
+        "{}.{}",
+        filename.trim_end_matches(".lol"),
+        options.target.extension()
+    );
+    //text is previewed on stdout rather than written to a file
+    let to_stdout = options.target == Target::Text;
+    if to_stdout {
+        options.color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    } else {
+        println!("{}", output_file_name);
+    }
+    options.source_dir = Path::new(filename).parent().map(Path::to_path_buf);
+
     //Running the compiler, entry point
-    let mut c = LolCompiler::new(&contents, outputFileName.clone());
-    c.compile(&contents);
-    
+    let output = match compile_str(&contents, &options) {
+        Ok(output) => output,
+        Err(diagnostics) => report(&diagnostics, json_errors, &contents, filename),
+    };
+    if to_stdout {
+        print!("{}", output);
+        return;
+    }
+    if let Err(e) = fs::write(&output_file_name, output) {
+        let error = CompileError::Io {
+            message: format!("cannot write {}: {}", output_file_name, e),
+        };
+        report(&[error.into()], json_errors, &contents, filename);
+    }
 
     //configuration to open and present HTML after processing
     #[cfg(target_os = "macos")]
-    {
+    if options.target == Target::Html {
         use std::process::Command;
         let _ = Command::new("open")
             .arg("-a")
             .arg("Google Chrome")
-            .arg(format!("./{}", outputFileName))
+            .arg(format!("./{}", output_file_name))
             .status();
     }
 }
+
+//The source file's contents; a file that cannot be read is reported like any other error.
+fn read_source(filename: &str, json_errors: bool) -> String {
+    fs::read_to_string(filename).unwrap_or_else(|e| {
+        let error = CompileError::Io {
+            message: format!("cannot read {}: {}", filename, e),
+        };
+        report(&[error.into()], json_errors, "", filename)
+    })
+}
+
+//Prints every diagnostic to stderr and exits with a failure status.
+fn report(diagnostics: &[Diagnostic], json_errors: bool, contents: &str, filename: &str) -> ! {
+    for d in diagnostics {
+        if json_errors {
+            eprintln!("{}", d.to_json(filename));
+        } else {
+            eprintln!("{}", d.render(contents, filename));
+        }
+    }
+    process::exit(1);
+}
+
+//Takes the value of `flag` either from `--flag=value` or from the next argument.
+fn option_value<'a>(arg: &str, flag: &str, rest: &mut impl Iterator<Item = &'a String>) -> String {
+    if arg == flag {
+        match rest.next() {
+            Some(value) => value.clone(),
+            None => {
+                eprintln!("{} needs a value", flag);
+                process::exit(1);
+            }
+        }
+    } else {
+        arg[flag.len() + 1..].to_string()
+    }
+}
+
+fn parse_target(name: &str) -> Target {
+    match name.parse() {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_width(value: &str) -> usize {
+    match value.parse() {
+        Ok(width) if width > 0 => width,
+        _ => {
+            eprintln!("--width must be a positive number of columns, not `{}`", value);
+            process::exit(1);
+        }
+    }
+}
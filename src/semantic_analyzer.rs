@@ -1,13 +1,24 @@
 use std::collections::HashMap;
+use crate::diagnostic::Diagnostic;
+use crate::error::CompileError;
+use crate::span::Span;
 use crate::syntax_analyzer::AST;
 pub struct SemanticAnalyzer {
     scopes: Vec<HashMap<String, String>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Default for SemanticAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SemanticAnalyzer {
     pub fn new() -> Self {
         SemanticAnalyzer {
             scopes: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
@@ -18,11 +29,18 @@ impl SemanticAnalyzer {
     fn pop_scope(&mut self) {
         self.scopes.pop();
     }
-    // 
-    pub fn check_program(&mut self, root: &AST) {
+    /// Checks the whole tree, collecting every problem rather than stopping at the first.
+    /// On success returns the resolved tree that backends render, in which every
+    /// `AST::VarUse` carries the value of the variable it names.
+    pub fn check_program(&mut self, root: &AST) -> Result<AST, Vec<Diagnostic>> {
         self.push_scope();
-        self.visit(root);
+        let resolved = self.visit(root);
         self.pop_scope();
+        if self.diagnostics.is_empty() {
+            Ok(resolved)
+        } else {
+            Err(std::mem::take(&mut self.diagnostics))
+        }
     }
 
     fn define(&mut self, name: &str, value: &str) {
@@ -40,164 +58,206 @@ impl SemanticAnalyzer {
         None
     }
 //Each element is appended to its' subarray which represents a scope.
-    fn visit(&mut self, node: &AST) {
+//Returns a copy of the node with its variable uses resolved.
+    fn visit(&mut self, node: &AST) -> AST {
         match node {
-            AST::Program { parts } => {
-                for p in parts {
-                    self.visit(p);
-                }
-            }
+            AST::Program { parts, span } => AST::Program {
+                parts: parts.iter().map(|p| self.visit(p)).collect(),
+                span: *span,
+            },
 
-            AST::Paragraph { items }
-            | AST::List { items }
-            | AST::ListItem { items } => {
-                self.push_scope();
-                for item in items {
-                    self.visit(item);
+            AST::Paragraph { items, span } => AST::Paragraph {
+                items: self.visit_block(items),
+                span: *span,
+            },
+            AST::Heading { level, items, span } => {
+                if !(1..=6).contains(level) {
+                    self.diagnostics.push(Self::bad_heading_level(*level, *span).into());
+                }
+                AST::Heading {
+                    level: *level,
+                    items: self.visit_block(items),
+                    span: *span,
                 }
-                self.pop_scope();
             }
-
-            AST::List { items } => {
-                self.push_scope();
-                for item in items {
-                    self.visit(item);
+            AST::Bold(items, span) => {
+                AST::Bold(items.iter().map(|it| self.visit(it)).collect(), *span)
+            }
+            AST::Italics(items, span) => {
+                AST::Italics(items.iter().map(|it| self.visit(it)).collect(), *span)
+            }
+            AST::Link { url, items, span } => {
+                self.check_url(url, false, *span);
+                AST::Link {
+                    url: url.clone(),
+                    items: items.iter().map(|it| self.visit(it)).collect(),
+                    span: *span,
                 }
-                self.pop_scope();
             }
+            AST::Image { url, span, .. } => {
+                self.check_url(url, true, *span);
+                node.clone()
+            }
+            AST::Audio(url, span) | AST::Video(url, span) => {
+                self.check_url(url, false, *span);
+                node.clone()
+            }
+            AST::List {
+                ordered,
+                start,
+                items,
+                span,
+            } => AST::List {
+                ordered: *ordered,
+                start: *start,
+                items: self.visit_block(items),
+                span: *span,
+            },
+            AST::ListItem { items, span } => AST::ListItem {
+                items: self.visit_block(items),
+                span: *span,
+            },
 
-            AST::ListItem { items } => {
-                self.push_scope();
-                for item in items {
-                    self.visit(item);
+            AST::Table { rows, span } => {
+                self.check_columns(rows);
+                AST::Table {
+                    rows: self.visit_block(rows),
+                    span: *span,
                 }
-                self.pop_scope();
             }
+            AST::Row { header, cells, span } => AST::Row {
+                header: *header,
+                cells: cells.iter().map(|c| self.visit(c)).collect(),
+                span: *span,
+            },
+            AST::Cell { items, span } => AST::Cell {
+                items: self.visit_block(items),
+                span: *span,
+            },
+            AST::Quote { items, cite, span } => AST::Quote {
+                items: self.visit_block(items),
+                cite: self.visit_block(cite),
+                span: *span,
+            },
 
-            AST::VarDefine { name, value } => {
+            AST::VarDefine { name, value, .. } => {
                 self.define(name, value);
+                node.clone()
             }
             //For each variable use found it will check that it exists within its' own block/scope,
             //then parent scope and so on.
-           AST::VarUse(name) => {
-                if self.lookup(name).is_none() {
-                    eprintln!(
-                        "Static scope error: variable '{}' used before it was defined (or out of scope).",
-                        name
-                    );
-                    std::process::exit(1);
+            AST::VarUse { name, span, .. } => {
+                let value = self.lookup(name);
+                if value.is_none() {
+                    self.diagnostics
+                        .push(Self::undefined_variable(name, *span).into());
+                }
+                AST::VarUse {
+                    name: name.clone(),
+                    value,
+                    span: *span,
                 }
             }
 
-            AST::Head { .. } => {}
-            AST::Comment(_) => {}
-            AST::Text(_) => {}
-            AST::Bold(_) => {}
-            AST::Italics(_) => {}
-            AST::Audio(_) => {}
-            AST::Video(_) => {}
-            AST::Newline => {}
+            AST::Head { .. }
+            | AST::Comment(..)
+            | AST::Text(..)
+            | AST::CodeBlock { .. }
+            | AST::Rule(_)
+            | AST::Newline(_) => node.clone(),
         }
     }
-    /*Generates HTML code based on the Parse Tree. For each lolcode element,
-    it will append an equivalent HTML elements to a output string.
-    */
-    fn parse_lolcode_html(&mut self, node: &AST, out: &mut String) {
-        match node {
-            AST::Program { parts } => {
-                out.push_str("<html>\n");
-                for p in parts {
-                    self.parse_lolcode_html(p, out);
-                }
-                out.push_str("</html>\n");
-            }
-
-            AST::Comment(txt) => {
-                out.push_str("<!-- ");
-                out.push_str(txt);
-                out.push_str(" -->\n");
-            }
 
-            AST::Head { title } => {
-                out.push_str("<head>\n<title>");
-                out.push_str(title);
-                out.push_str("</title>\n</head>\n");
-            }
+    //the children of a block element live in a scope of their own
+    fn visit_block(&mut self, items: &[AST]) -> Vec<AST> {
+        self.push_scope();
+        let resolved = items.iter().map(|item| self.visit(item)).collect();
+        self.pop_scope();
+        resolved
+    }
 
-            AST::Paragraph { items } => {
-                out.push_str("<p>");
-                self.push_scope();
-                for it in items {
-                    self.parse_lolcode_html(it, out);
-                }
-                self.pop_scope();
-                out.push_str("</p>\n");
-            }
-            AST::List { items } => {
-                out.push_str("<ul>\n");
-                self.push_scope();
-                for it in items {
-                    self.parse_lolcode_html(it, out);
-                }
-                self.pop_scope();
-                out.push_str("</ul>\n");
-            }
-            AST::ListItem { items } => {
-                out.push_str("<li>");
-                self.push_scope();
-                for it in items {
-                    self.parse_lolcode_html(it, out);
-                }
-                self.pop_scope();
-                out.push_str("</li>\n");
+    //every row must have as many cells as the first
+    fn check_columns(&mut self, rows: &[AST]) {
+        let mut widths = rows.iter().filter_map(|row| match row {
+            AST::Row { cells, span, .. } => Some((cells.len(), *span)),
+            _ => None,
+        });
+        let Some((columns, _)) = widths.next() else {
+            return;
+        };
+        for (width, span) in widths {
+            if width != columns {
+                self.diagnostics.push(Self::ragged_row(width, columns, span).into());
             }
+        }
+    }
 
-            AST::Bold(txt) => {
-                out.push_str("<b>");
-                out.push_str(txt);
-                out.push_str("</b>");
-            }
-            AST::Italics(txt) => {
-                out.push_str("<i>");
-                out.push_str(txt);
-                out.push_str("</i>");
-            }
-            AST::Audio(url) => {
-                out.push_str("<audio controls>\n<source src=\"");
-                out.push_str(url);
-                out.push_str("\">\n</audio>");
-            }
-            AST::Video(url) => {
-                out.push_str("<iframe src=\"");
-                out.push_str(url);
-                out.push_str("\"></iframe>\n");
-            }
-            AST::Newline => out.push_str("<br>\n"),
+    //A page must not run code when a link is followed or media is loaded, so
+    //script and data URLs are refused; an image may still be a data URL of an
+    //image, which browsers never run. Browsers ignore tabs and line breaks in a
+    //URL and the case of its scheme, so the check does too.
+    fn check_url(&mut self, url: &str, image: bool, span: Span) {
+        let url: String = url
+            .trim()
+            .chars()
+            .filter(|c| !c.is_ascii_control())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let Some((scheme, rest)) = url.split_once(':') else {
+            return;
+        };
+        let allowed = match scheme {
+            "javascript" | "vbscript" => false,
+            "data" => image && rest.trim_start().starts_with("image/"),
+            _ => true,
+        };
+        if !allowed {
+            self.diagnostics.push(Self::unsafe_url(scheme, span).into());
+        }
+    }
 
-            AST::Text(t) => {
-                out.push_str(t);
-                if !t.is_empty() {
-                    out.push(' ');
-                }
-            }
+    fn undefined_variable(name: &str, span: Span) -> CompileError {
+        CompileError::Semantic {
+            code: "E0201",
+            span,
+            message: format!("variable `{}` is not defined in this scope", name),
+            help: Some(format!(
+                "define it first with `#I HAZ {} #IT IZ ... #MKAY` in this block or an enclosing one",
+                name
+            )),
+        }
+    }
 
-            AST::VarDefine { name, value } => {
-                self.define(name, value);
-            }
+    fn ragged_row(cells: usize, columns: usize, span: Span) -> CompileError {
+        CompileError::Semantic {
+            code: "E0203",
+            span,
+            message: format!(
+                "this row has {} cell{} but the table has {} column{}",
+                cells,
+                if cells == 1 { "" } else { "s" },
+                columns,
+                if columns == 1 { "" } else { "s" }
+            ),
+            help: Some("give every row of a table the same number of `#GIMMEH CELL`s".to_string()),
+        }
+    }
 
-            AST::VarUse(name) => {
-                if let Some(v) = self.lookup(name) {
-                    out.push_str(&v);
-                }
-            }
+    fn bad_heading_level(level: u32, span: Span) -> CompileError {
+        CompileError::Semantic {
+            code: "E0202",
+            span,
+            message: format!("heading level {} does not exist", level),
+            help: Some("use a level from 1 (the largest) to 6".to_string()),
         }
     }
 
-    pub fn generate(&mut self, ast: &AST) -> String {
-        let mut html = String::new();
-        self.push_scope();                
-        self.parse_lolcode_html(ast, &mut html);
-        self.pop_scope();
-        html
+    fn unsafe_url(scheme: &str, span: Span) -> CompileError {
+        CompileError::Semantic {
+            code: "E0204",
+            span,
+            message: format!("`{}:` URLs are not allowed, as they can run code", scheme),
+            help: Some("link to an `http:` or `https:` address, or a relative path".to_string()),
+        }
     }
-}
\ No newline at end of file
+}
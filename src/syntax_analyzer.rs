@@ -1,106 +1,510 @@
-use crate::lexer::LolLexer;
+use crate::diagnostic::Diagnostic;
+use crate::error::CompileError;
+use crate::json;
+use crate::lexer::{Lexeme, LolLexer};
+use crate::span::Span;
+use crate::token::{Annotation, Token};
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 pub enum AST {
-    Program { parts: Vec<AST> },
-    Comment(String),
-    Head { title: String },
-    Text(String),
-    Paragraph { items: Vec<AST> },
-    Bold(String),
-    Italics(String),
-    List { items: Vec<AST> },
-    ListItem { items: Vec<AST> },
-    Audio(String),
-    Video(String),
-    Newline,
-    VarDefine { name: String, value: String },
-    VarUse(String),
+    Program { parts: Vec<AST>, span: Span },
+    Comment(String, Span),
+    /// The document metadata from `#MAEK HEAD`. `keywords` collects every
+    /// `#GIMMEH KEYWORDS` list and `styles` the `#GIMMEH STYLE` stylesheets,
+    /// in source order.
+    Head {
+        title: String,
+        author: Option<String>,
+        description: Option<String>,
+        keywords: Vec<String>,
+        language: Option<String>,
+        favicon: Option<String>,
+        styles: Vec<String>,
+        span: Span,
+    },
+    /// A run of words with the whitespace in and around it as written. Only
+    /// whitespace between two pieces of inline content is kept, so there is
+    /// none next to an opening or closing annotation or a block.
+    Text(String, Span),
+    Paragraph { items: Vec<AST>, span: Span },
+    /// `#GIMMEH HEADING n ... #MKAY`. The level is checked by the semantic analyzer.
+    Heading { level: u32, items: Vec<AST>, span: Span },
+    Bold(Vec<AST>, Span),
+    Italics(Vec<AST>, Span),
+    /// `#MAEK LIST`, or with `ordered` `#MAEK NUMBRD LIST`, which may give the
+    /// number of its first item as `start`.
+    List { ordered: bool, start: Option<u32>, items: Vec<AST>, span: Span },
+    ListItem { items: Vec<AST>, span: Span },
+    /// `#MAEK TABL`; `rows` are `AST::Row`s, each holding `AST::Cell`s.
+    Table { rows: Vec<AST>, span: Span },
+    /// `#GIMMEH ROW`, or with `header` `#GIMMEH HEDR ROW`.
+    Row { header: bool, cells: Vec<AST>, span: Span },
+    Cell { items: Vec<AST>, span: Span },
+    /// `#MAEK KODE [lang] ... #OIC`, with `text` exactly as written.
+    CodeBlock { lang: Option<String>, text: String, span: Span },
+    /// `#MAEK KWOTE ... #OIC`. `cite` is the `#GIMMEH SEZ ... #MKAY` attribution
+    /// that may end it, and is empty without one.
+    Quote { items: Vec<AST>, cite: Vec<AST>, span: Span },
+    /// `#GIMMEH LINE #MKAY`, a horizontal rule.
+    Rule(Span),
+    Audio(String, Span),
+    Video(String, Span),
+    /// `items` is the link text; an empty link shows its URL.
+    Link { url: String, items: Vec<AST>, span: Span },
+    Image { url: String, alt: String, span: Span },
+    Newline(Span),
+    VarDefine { name: String, value: String, span: Span },
+    /// `value` is filled in by `SemanticAnalyzer::check_program`; the parser leaves it `None`.
+    VarUse {
+        name: String,
+        value: Option<String>,
+        span: Span,
+    },
+}
+
+impl AST {
+    /// Where this node was written in the source.
+    pub fn span(&self) -> Span {
+        match self {
+            AST::Program { span, .. }
+            | AST::Head { span, .. }
+            | AST::Paragraph { span, .. }
+            | AST::Heading { span, .. }
+            | AST::Link { span, .. }
+            | AST::Image { span, .. }
+            | AST::List { span, .. }
+            | AST::ListItem { span, .. }
+            | AST::Table { span, .. }
+            | AST::Row { span, .. }
+            | AST::Cell { span, .. }
+            | AST::CodeBlock { span, .. }
+            | AST::Quote { span, .. }
+            | AST::VarDefine { span, .. }
+            | AST::VarUse { span, .. } => *span,
+            AST::Comment(_, span)
+            | AST::Text(_, span)
+            | AST::Bold(_, span)
+            | AST::Italics(_, span)
+            | AST::Audio(_, span)
+            | AST::Video(_, span) => *span,
+            AST::Newline(span) | AST::Rule(span) => *span,
+        }
+    }
+
+    /// The node and its children as a JSON object for `--emit ast-json`:
+    /// `{"kind": variant name, "span": ..., ...}` plus the variant's fields,
+    /// with child nodes under `children`.
+    pub fn to_json(&self) -> String {
+        let fields = match self {
+            AST::Program { parts: children, .. }
+            | AST::Paragraph { items: children, .. }
+            | AST::ListItem { items: children, .. }
+            | AST::Table { rows: children, .. }
+            | AST::Cell { items: children, .. }
+            | AST::Bold(children, _)
+            | AST::Italics(children, _) => {
+                let children: Vec<String> = children.iter().map(AST::to_json).collect();
+                format!(",\"children\":[{}]", children.join(","))
+            }
+            AST::CodeBlock { lang, text, .. } => format!(
+                ",\"lang\":{},\"text\":{}",
+                json::opt_string(lang.as_deref()),
+                json::string(text)
+            ),
+            AST::Row { header, cells, .. } => {
+                let children: Vec<String> = cells.iter().map(AST::to_json).collect();
+                format!(",\"header\":{},\"children\":[{}]", header, children.join(","))
+            }
+            AST::List { ordered, start, items, .. } => {
+                let children: Vec<String> = items.iter().map(AST::to_json).collect();
+                let start = match start {
+                    Some(n) => n.to_string(),
+                    None => "null".to_string(),
+                };
+                format!(
+                    ",\"ordered\":{},\"start\":{},\"children\":[{}]",
+                    ordered,
+                    start,
+                    children.join(",")
+                )
+            }
+            AST::Heading { level, items, .. } => {
+                let children: Vec<String> = items.iter().map(AST::to_json).collect();
+                format!(",\"level\":{},\"children\":[{}]", level, children.join(","))
+            }
+            AST::Quote { items, cite, .. } => {
+                let children: Vec<String> = items.iter().map(AST::to_json).collect();
+                let cite: Vec<String> = cite.iter().map(AST::to_json).collect();
+                format!(",\"children\":[{}],\"cite\":[{}]", children.join(","), cite.join(","))
+            }
+            AST::Comment(text, _) | AST::Text(text, _) => {
+                format!(",\"text\":{}", json::string(text))
+            }
+            AST::Head {
+                title,
+                author,
+                description,
+                keywords,
+                language,
+                favicon,
+                styles,
+                ..
+            } => {
+                let list = |items: &[String]| {
+                    let items: Vec<String> = items.iter().map(|s| json::string(s)).collect();
+                    format!("[{}]", items.join(","))
+                };
+                format!(
+                    ",\"title\":{},\"author\":{},\"description\":{},\"keywords\":{},\"language\":{},\"favicon\":{},\"styles\":{}",
+                    json::string(title),
+                    json::opt_string(author.as_deref()),
+                    json::opt_string(description.as_deref()),
+                    list(keywords),
+                    json::opt_string(language.as_deref()),
+                    json::opt_string(favicon.as_deref()),
+                    list(styles)
+                )
+            }
+            AST::Audio(url, _) | AST::Video(url, _) => {
+                format!(",\"url\":{}", json::string(url))
+            }
+            AST::Link { url, items, .. } => {
+                let children: Vec<String> = items.iter().map(AST::to_json).collect();
+                format!(",\"url\":{},\"children\":[{}]", json::string(url), children.join(","))
+            }
+            AST::Image { url, alt, .. } => {
+                format!(",\"url\":{},\"alt\":{}", json::string(url), json::string(alt))
+            }
+            AST::Newline(_) | AST::Rule(_) => String::new(),
+            AST::VarDefine { name, value, .. } => format!(
+                ",\"name\":{},\"value\":{}",
+                json::string(name),
+                json::string(value)
+            ),
+            AST::VarUse { name, value, .. } => format!(
+                ",\"name\":{},\"value\":{}",
+                json::string(name),
+                json::opt_string(value.as_deref())
+            ),
+        };
+        format!(
+            "{{\"kind\":{},\"span\":{}{}}}",
+            json::string(self.kind()),
+            self.span().to_json(),
+            fields
+        )
+    }
+
+    /// The variant name.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AST::Program { .. } => "Program",
+            AST::Comment(..) => "Comment",
+            AST::Head { .. } => "Head",
+            AST::Text(..) => "Text",
+            AST::Paragraph { .. } => "Paragraph",
+            AST::Heading { .. } => "Heading",
+            AST::Bold(..) => "Bold",
+            AST::Italics(..) => "Italics",
+            AST::List { .. } => "List",
+            AST::ListItem { .. } => "ListItem",
+            AST::Table { .. } => "Table",
+            AST::Row { .. } => "Row",
+            AST::Cell { .. } => "Cell",
+            AST::CodeBlock { .. } => "CodeBlock",
+            AST::Quote { .. } => "Quote",
+            AST::Rule(_) => "Rule",
+            AST::Audio(..) => "Audio",
+            AST::Video(..) => "Video",
+            AST::Link { .. } => "Link",
+            AST::Image { .. } => "Image",
+            AST::Newline(_) => "Newline",
+            AST::VarDefine { .. } => "VarDefine",
+            AST::VarUse { .. } => "VarUse",
+        }
+    }
 }
 
 pub trait SyntaxAnalyzer {
-    fn parse_lolcode(&mut self);
-    fn parse_head(&mut self);
-    fn parse_title(&mut self);
-    fn parse_comment(&mut self);
-    fn parse_body(&mut self);
-    fn parse_paragraph(&mut self);
-    fn parse_inner_paragraph(&mut self);
-    fn parse_inner_text(&mut self);
-    fn parse_variable_define(&mut self);
-    fn parse_variable_use(&mut self);
-    fn parse_bold(&mut self);
-    fn parse_italics(&mut self);
-    fn parse_list(&mut self);
-    fn parse_list_items(&mut self);
-    fn parse_inner_list(&mut self);
-    fn parse_audio(&mut self);
-    fn parse_video(&mut self);
-    fn parse_newline(&mut self);
-    fn parse_text(&mut self);
+    fn parse_lolcode(&mut self) -> Result<(), CompileError>;
+    fn parse_head(&mut self) -> Result<(), CompileError>;
+    fn parse_title(&mut self) -> Result<(), CompileError>;
+    fn parse_head_field(&mut self) -> Result<(), CompileError>;
+    fn parse_comment(&mut self) -> Result<(), CompileError>;
+    fn parse_body(&mut self) -> Result<(), CompileError>;
+    fn parse_paragraph(&mut self) -> Result<(), CompileError>;
+    fn parse_inner_paragraph(&mut self) -> Result<(), CompileError>;
+    fn parse_inner_text(&mut self) -> Result<(), CompileError>;
+    fn parse_heading(&mut self) -> Result<(), CompileError>;
+    fn parse_inline(&mut self) -> Result<(), CompileError>;
+    fn parse_variable_define(&mut self) -> Result<(), CompileError>;
+    fn parse_variable_use(&mut self) -> Result<(), CompileError>;
+    fn parse_bold(&mut self) -> Result<(), CompileError>;
+    fn parse_italics(&mut self) -> Result<(), CompileError>;
+    fn parse_list(&mut self) -> Result<(), CompileError>;
+    fn parse_list_items(&mut self) -> Result<(), CompileError>;
+    fn parse_inner_list(&mut self) -> Result<(), CompileError>;
+    fn parse_table(&mut self) -> Result<(), CompileError>;
+    fn parse_table_rows(&mut self) -> Result<(), CompileError>;
+    fn parse_table_cells(&mut self) -> Result<(), CompileError>;
+    fn parse_code_block(&mut self) -> Result<(), CompileError>;
+    fn parse_quote(&mut self) -> Result<(), CompileError>;
+    fn parse_rule(&mut self) -> Result<(), CompileError>;
+    fn parse_audio(&mut self) -> Result<(), CompileError>;
+    fn parse_video(&mut self) -> Result<(), CompileError>;
+    fn parse_link(&mut self) -> Result<(), CompileError>;
+    fn parse_image(&mut self) -> Result<(), CompileError>;
+    fn parse_newline(&mut self) -> Result<(), CompileError>;
+    fn parse_text(&mut self) -> Result<(), CompileError>;
 }
 
 pub struct LolCodeSyntaxAnalyzer {
-    pub tokens: Vec<String>,
+    pub tokens: Vec<Lexeme>,
     pub position: usize,
     pub ast: Vec<AST>,
+    pub diagnostics: Vec<Diagnostic>,
+    open_blocks: Vec<OpenBlock>,
+    //set while parsing the text of a link, which cannot hold another link
+    in_link: bool,
+}
+
+//a construct that has been opened and is waiting for its closing token
+struct OpenBlock {
+    closer: Token,
+    opener: String,
+    line: usize,
+    //where its children start in the tree
+    start: usize,
 }
 
 impl LolCodeSyntaxAnalyzer {
     //Using the lexer to get all of the valid tokens
-    pub fn new(mut lexer: LolLexer) -> Self {
-        let mut tokens: Vec<String> = Vec::new();
-        let mut t = lexer.next_token();
-        while t != "EOF" {
+    pub fn new(mut lexer: LolLexer) -> Result<Self, CompileError> {
+        let mut tokens: Vec<Lexeme> = Vec::new();
+        let mut t = lexer.next_token()?;
+        while t.token != Token::Eof {
             tokens.push(t.clone());
-            t = lexer.next_token();
+            t = lexer.next_token()?;
         }
         tokens.push(t);
+        Ok(Self::from_tokens(tokens))
+    }
+
+    /// Parser over an already scanned token stream, which should end with `Token::Eof`.
+    pub fn from_tokens(tokens: Vec<Lexeme>) -> Self {
         LolCodeSyntaxAnalyzer {
-            tokens: tokens,
+            tokens,
             position: 0,
             ast: Vec::new(),
+            diagnostics: Vec::new(),
+            open_blocks: Vec::new(),
+            in_link: false,
         }
     }
     ///Helper function to check if each token matches the expected token
-    fn expect(&mut self, tok: &str) {
-        let current_token = self.current().to_string();
-        if current_token == tok {
+    fn expect(&mut self, tok: Token) -> Result<(), CompileError> {
+        if *self.current() == tok {
             self.position += 1;
+            Ok(())
         } else {
-            self.error(tok, "expect()");
+            Err(self.error(&format!("`{}`", tok)))
         }
     }
 
-    fn error(&self, excep_token: &str, func_from: &str) {
-        eprintln!(
-            "Syntax error near position {}. Expected {} token but found {}",
-            self.position,
-            excep_token,
-            if self.position < self.tokens.len() {
-                &self.tokens[self.position]
-            } else {
-                "EOF"
-            }
+    fn error(&self, expected: &str) -> CompileError {
+        // a missing closer is the most common mistake, so point back at what it should close
+        let help = match self.open_blocks.last() {
+            Some(b) if expected == format!("`{}`", b.closer) => Some(format!(
+                "did you forget `{}` to close `{}` opened on line {}?",
+                b.closer, b.opener, b.line
+            )),
+            _ => None,
+        };
+        CompileError::Syntax {
+            code: if help.is_some() { "E0102" } else { "E0101" },
+            span: self.current_span(),
+            message: format!("expected {}, found {}", expected, Self::describe(self.current())),
+            help,
+        }
+    }
+
+    //how a token is named in error messages
+    fn describe(tok: &Token) -> String {
+        match tok {
+            Token::Text(s) => format!("text `{}`", s),
+            Token::Verbatim(_) => "code".to_string(),
+            Token::Eof => "end of file".to_string(),
+            t => format!("`{}`", t),
+        }
+    }
+
+    //called at the first token of a construct that must later see `closer`
+    fn open(&mut self, closer: Token) {
+        let opener = match self.next() {
+            Token::Keyword(a) => format!("{} {}", self.current(), a.as_str()),
+            _ => self.current().to_string(),
+        };
+        let line = self.current_span().line;
+        self.open_blocks.push(OpenBlock {
+            closer,
+            opener,
+            line,
+            start: self.ast.len(),
+        });
+    }
+
+    //consumes the closing token of the innermost open construct
+    fn close(&mut self, tok: Token) -> Result<(), CompileError> {
+        self.expect(tok)?;
+        self.open_blocks.pop();
+        Ok(())
+    }
+
+    fn current(&self) -> &Token {
+        self.peek_at(self.position)
+    }
+
+    //the token after the current one, used to tell annotations apart
+    fn next(&self) -> &Token {
+        self.peek_at(self.position + 1)
+    }
+
+    fn peek_at(&self, i: usize) -> &Token {
+        match self.tokens.get(i) {
+            Some(t) => &t.token,
+            None => &Token::Eof,
+        }
+    }
+
+    //span of the current token; past the end this is the span of the last token
+    fn current_span(&self) -> Span {
+        match self.tokens.get(self.position).or(self.tokens.last()) {
+            Some(t) => t.span,
+            None => Span::default(),
+        }
+    }
+
+    //span of the token just consumed, used to close off a construct's span
+    fn previous_span(&self) -> Span {
+        match self.position.checked_sub(1).and_then(|i| self.tokens.get(i)) {
+            Some(t) => t.span,
+            None => self.current_span(),
+        }
+    }
+
+    //the token that ends the construct starting at the current token
+    fn closer(&self) -> Token {
+        match self.current() {
+            Token::Maek => Token::Oic,
+            Token::Obtw => Token::Tldr,
+            _ => Token::Mkay,
+        }
+    }
+
+    //the node before the one being parsed within the innermost open construct
+    fn previous_sibling(&self) -> Option<&AST> {
+        let start = self.open_blocks.last().map_or(0, |b| b.start);
+        self.ast.get(start..)?.last()
+    }
+
+    //Keeps the whitespace before the current token as text when it separates
+    //two pieces of inline content, joined to the text before it if there is any.
+    fn parse_space(&mut self) {
+        if !self.previous_sibling().is_some_and(is_inline) {
+            return;
+        }
+        let space = match self.tokens.get(self.position) {
+            Some(t) if !t.space.is_empty() => t.space.clone(),
+            _ => return,
+        };
+        //tokens other than code are on one line, so this is where the last one ends
+        let previous = self.previous_span();
+        let span = Span::new(
+            previous.end,
+            self.current_span().start,
+            previous.line,
+            previous.col + previous.len(),
         );
-        std::process::exit(1);
+        match self.ast.last_mut() {
+            Some(AST::Text(text, text_span)) => {
+                text.push_str(&space);
+                *text_span = text_span.to(span);
+            }
+            _ => self.ast.push(AST::Text(space, span)),
+        }
     }
 
-    fn current(&self) -> &str {
-        if self.position < self.tokens.len() {
-            &self.tokens[self.position]
+    // `#GIMMEH <annotation> words... #MKAY` inside the head, pushed as one text node
+    fn parse_annotated_text(&mut self, annotation: Annotation) -> Result<(), CompileError> {
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(annotation))?;
+
+        let mut parts: Vec<String> = Vec::new();
+        while let Token::Text(txt) = self.current() {
+            parts.push(txt.clone());
+            self.position += 1;
+        }
+
+        self.close(Token::Mkay)?;
+
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Text(parts.join(" "), span));
+        Ok(())
+    }
+
+    //a head annotation that may only be given once; a repeat is reported and ignored
+    fn set_once(&mut self, slot: &mut Option<String>, value: String, annotation: Annotation, span: Span) {
+        if slot.is_some() {
+            self.diagnostics.push(
+                CompileError::Syntax {
+                    code: "E0103",
+                    span,
+                    message: format!("`{}` is given more than once in the head", annotation.as_str()),
+                    help: Some("only the first one is used; remove this one".to_string()),
+                }
+                .into(),
+            );
         } else {
-            "EOF"
+            *slot = Some(value);
         }
     }
 
-    fn is_text(&self, tok: &str) -> bool {
-        tok.starts_with("TEXT(") && tok.ends_with(')')
+    //how far the tree and the open constructs have got, to roll back to on an error
+    fn checkpoint(&self) -> (usize, usize) {
+        (self.ast.len(), self.open_blocks.len())
     }
 
-    fn text_content(tok: &str) -> String {
-        tok.trim_start_matches("TEXT(")
-            .trim_end_matches(')')
-            .to_string()
+    ///Records a syntax error and resynchronises so parsing can carry on. Whatever the
+    ///failed construct had added since `mark` was taken is thrown away.
+    fn recover(&mut self, err: CompileError, mark: (usize, usize), closer: Token) {
+        self.diagnostics.push(err.into());
+        self.ast.truncate(mark.0);
+        self.open_blocks.truncate(mark.1);
+        self.synchronize(closer);
+    }
+
+    ///Skips ahead past `closer`, the token ending the construct that failed to parse.
+    ///Stops without consuming at a boundary that belongs to an enclosing construct:
+    ///#KTHXBYE always, and #OIC when looking for #MKAY. Blocks opened while skipping
+    ///are matched up so their #OIC is not mistaken for ours.
+    fn synchronize(&mut self, closer: Token) {
+        let mut depth = 0;
+        loop {
+            match self.current() {
+                Token::KthxBye | Token::Eof => return,
+                Token::Maek => depth += 1,
+                Token::Oic if depth > 0 => depth -= 1,
+                Token::Oic if closer == Token::Mkay => return,
+                tok if *tok == closer => {
+                    self.position += 1;
+                    return;
+                }
+                _ => {}
+            }
+            self.position += 1;
+        }
     }
 }
 
@@ -109,304 +513,727 @@ impl SyntaxAnalyzer for LolCodeSyntaxAnalyzer {
     It implements a recursive analyzer, and checks if the source following the correct grammer.
     If the is correct, it will append the tokens to a parse tree for the semantic analysis phase.
     */
-    fn parse_lolcode(&mut self) {
+    fn parse_lolcode(&mut self) -> Result<(), CompileError> {
         // remember where this program starts in AST
         let start_len = self.ast.len();
+        let start = self.current_span();
 
-        self.expect("#HAI");
+        if let Err(e) = self.expect(Token::Hai) {
+            self.diagnostics.push(e.into());
+        }
 
-        while self.current() == "#OBTW" {
-            self.parse_comment();
+        while *self.current() == Token::Obtw {
+            let mark = self.checkpoint();
+            if let Err(e) = self.parse_comment() {
+                self.recover(e, mark, Token::Tldr);
+            }
         }
 
-        if self.current() == "#MAEK" {
-            self.parse_head();
+        if (self.current(), self.next()) == (&Token::Maek, &Token::Keyword(Annotation::Head)) {
+            let mark = self.checkpoint();
+            if let Err(e) = self.parse_head() {
+                self.recover(e, mark, Token::Oic);
+            }
         }
 
-        self.parse_body();
-        self.expect("#KTHXBYE");
-        self.expect("EOF");
-        let parts: Vec<AST> = self.ast.drain(start_len..).collect();
-        self.ast.push(AST::Program { parts });
-    }
+        // anything parse_body cannot place is reported and skipped until #KTHXBYE
+        loop {
+            self.parse_body()?;
+            if let Token::KthxBye | Token::Eof = self.current() {
+                break;
+            }
+            let err = self.error(&format!("`{}`", Token::KthxBye));
+            let before = self.position;
+            let closer = self.closer();
+            //step over a `#MAEK` or `#OBTW`, or synchronize takes it for a block
+            //nested in the one being skipped and runs on past its end
+            if closer != Token::Mkay {
+                self.position += 1;
+            }
+            self.recover(err, self.checkpoint(), closer);
+            if self.position == before {
+                self.position += 1;
+            }
+        }
 
-    fn parse_head(&mut self) {
-        self.expect("#MAEK");
-        self.expect("HEAD");
-        self.parse_title();
-        self.expect("#OIC");
+        if let Err(e) = self.expect(Token::KthxBye) {
+            self.diagnostics.push(e.into());
+        }
+        let span = start.to(self.previous_span());
+        if let Err(e) = self.expect(Token::Eof) {
+            self.diagnostics.push(e.into());
+        }
+        let parts: Vec<AST> = self.ast.drain(start_len..).collect();
+        self.ast.push(AST::Program { parts, span });
+        Ok(())
     }
 
-    fn parse_title(&mut self) {
-        self.expect("#GIMMEH");
-        self.expect("TITLE");
+    fn parse_head(&mut self) -> Result<(), CompileError> {
+        let start = self.current_span();
+        self.open(Token::Oic);
+        self.expect(Token::Maek)?;
+        self.expect(Token::Keyword(Annotation::Head))?;
 
-        let mut parts: Vec<String> = Vec::new();
-        while self.is_text(self.current()) {
-            let tok = self.current().to_string();
-            let txt = Self::text_content(&tok);
-            self.position += 1;
-            parts.push(txt);
+        // the annotations may come in any order; each is pushed as text and taken back here
+        let (mut title, mut author, mut description, mut language, mut favicon) =
+            (None, None, None, None, None);
+        let mut keywords: Vec<String> = Vec::new();
+        let mut styles: Vec<String> = Vec::new();
+        while let (Token::Gimmeh, Token::Keyword(annotation)) = (self.current(), self.next()) {
+            let annotation = *annotation;
+            if !annotation.is_head_field() {
+                break;
+            }
+            let field_start = self.current_span();
+            if annotation == Annotation::Title {
+                self.parse_title()?;
+            } else {
+                self.parse_head_field()?;
+            }
+            let value = match self.ast.pop() {
+                Some(AST::Text(s, _)) => s,
+                _ => String::new(),
+            };
+            let span = field_start.to(self.previous_span());
+            match annotation {
+                Annotation::Title => self.set_once(&mut title, value, annotation, span),
+                Annotation::Author => self.set_once(&mut author, value, annotation, span),
+                Annotation::Description => self.set_once(&mut description, value, annotation, span),
+                Annotation::Language => self.set_once(&mut language, value, annotation, span),
+                Annotation::Favicon => self.set_once(&mut favicon, value, annotation, span),
+                Annotation::Keywords => keywords.extend(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|k| !k.is_empty())
+                        .map(str::to_string),
+                ),
+                _ => styles.push(value),
+            }
         }
+        self.close(Token::Oic)?;
 
-        self.expect("#MKAY");
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Head {
+            title: title.unwrap_or_default(),
+            author,
+            description,
+            keywords,
+            language,
+            favicon,
+            styles,
+            span,
+        });
+        Ok(())
+    }
+
+    fn parse_title(&mut self) -> Result<(), CompileError> {
+        self.parse_annotated_text(Annotation::Title)
+    }
 
-        let title = parts.join(" ");
-        self.ast.push(AST::Head { title });
+    //any other head annotation: AUTHOR, DESCRIPTION, KEYWORDS, LANGUAGE, FAVICON or STYLE
+    fn parse_head_field(&mut self) -> Result<(), CompileError> {
+        match self.next() {
+            Token::Keyword(a) if a.is_head_field() => {
+                let annotation = *a;
+                self.parse_annotated_text(annotation)
+            }
+            _ => {
+                self.position += 1;
+                Err(self.error("a head annotation"))
+            }
+        }
     }
 
-    fn parse_comment(&mut self) {
-        self.expect("#OBTW");
+    fn parse_comment(&mut self) -> Result<(), CompileError> {
+        let start = self.current_span();
+        self.open(Token::Tldr);
+        self.expect(Token::Obtw)?;
 
         let mut parts: Vec<String> = Vec::new();
-        while self.is_text(self.current()) {
-            let tok = self.current().to_string();
-            let txt = Self::text_content(&tok);
-            parts.push(txt);
+        while let Token::Text(txt) = self.current() {
+            parts.push(txt.clone());
             self.position += 1;
         }
 
-        self.expect("#TLDR");
+        self.close(Token::Tldr)?;
 
         let comment_text = parts.join(" ");
-        self.ast.push(AST::Comment(comment_text));
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Comment(comment_text, span));
+        Ok(())
     }
 
-    fn parse_body(&mut self) {
+    fn parse_body(&mut self) -> Result<(), CompileError> {
         while self.position < self.tokens.len() {
-            let current = self.current();
-            let next_token = if self.position + 1 < self.tokens.len() {
-                &self.tokens[self.position + 1]
-            } else {
-                "EOF"
-            };
-
-            match (current, next_token) {
-                ("#MAEK", "PARAGRAF") => {
-                    self.parse_paragraph();
-                }
+            let mark = self.checkpoint();
+            let closer = self.closer();
+            let result = match (self.current(), self.next()) {
+                (Token::Maek, Token::Keyword(Annotation::Paragraf)) => self.parse_paragraph(),
 
-                ("#MAEK", "LIST") => {
-                    self.parse_list();
+                (Token::Maek, Token::Keyword(Annotation::List | Annotation::Numbrd)) => {
+                    self.parse_list()
                 }
-                ("#GIMMEH", "BOLD") => self.parse_bold(),
-                ("#GIMMEH", "ITALICS") => self.parse_italics(),
-                ("#GIMMEH", "NEWLINE") => self.parse_newline(),
-                ("#GIMMEH", "SOUNDZ") => self.parse_audio(),
-                ("#GIMMEH", "VIDZ") => self.parse_video(),
-                ("#I HAZ", _) => self.parse_variable_define(),
-                ("#LEMME SEE", _) => self.parse_variable_use(),
+                (Token::Maek, Token::Keyword(Annotation::Tabl)) => self.parse_table(),
+                (Token::Maek, Token::Keyword(Annotation::Kode)) => self.parse_code_block(),
+                (Token::Maek, Token::Keyword(Annotation::Kwote)) => self.parse_quote(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Heading)) => self.parse_heading(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Line)) => self.parse_rule(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Bold)) => self.parse_bold(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Italics)) => self.parse_italics(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Newline)) => self.parse_newline(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Soundz)) => self.parse_audio(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Vidz)) => self.parse_video(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Link)) => self.parse_link(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Pikchur)) => self.parse_image(),
+                (Token::IHaz, _) => self.parse_variable_define(),
+                (Token::LemmeSee, _) => self.parse_variable_use(),
 
-                (t, _) if self.is_text(t) => self.parse_text(),
+                (Token::Text(_), _) => self.parse_inner_text(),
 
-                ("#OBTW", _) => self.parse_comment(),
+                (Token::Obtw, _) => self.parse_comment(),
 
                 _ => break,
+            };
+            if let Err(e) = result {
+                self.recover(e, mark, closer);
             }
         }
+        Ok(())
     }
 
-    fn parse_paragraph(&mut self) {
+    fn parse_paragraph(&mut self) -> Result<(), CompileError> {
         // paragraph will collect its children
         let start_len = self.ast.len();
-        self.expect("#MAEK");
-        self.expect("PARAGRAF");
-        if self.current() == "#I HAZ" {
-            self.parse_variable_define();
+        let start = self.current_span();
+        self.open(Token::Oic);
+        self.expect(Token::Maek)?;
+        self.expect(Token::Keyword(Annotation::Paragraf))?;
+        if *self.current() == Token::IHaz {
+            self.parse_variable_define()?;
         }
-        self.parse_inner_paragraph();
-        self.expect("#OIC");
+        self.parse_inner_paragraph()?;
+        self.close(Token::Oic)?;
 
         let items: Vec<AST> = self.ast.drain(start_len..).collect();
-        self.ast.push(AST::Paragraph { items });
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Paragraph { items, span });
+        Ok(())
     }
 
-    fn parse_inner_paragraph(&mut self) {
+    fn parse_inner_paragraph(&mut self) -> Result<(), CompileError> {
         while self.position < self.tokens.len() {
-            let mut current = self.current();
-            let next_token = if self.position + 1 < self.tokens.len() {
-                self.tokens[self.position + 1].as_str()
-            } else {
-                ""
-            };
+            let mark = self.checkpoint();
+            let closer = self.closer();
+            let result = match (self.current(), self.next()) {
+                (Token::LemmeSee, _) => self.parse_variable_use(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Bold)) => self.parse_bold(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Italics)) => self.parse_italics(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Newline)) => self.parse_newline(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Soundz)) => self.parse_audio(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Vidz)) => self.parse_video(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Link)) => self.parse_link(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Pikchur)) => self.parse_image(),
+                (Token::Text(_), _) => self.parse_inner_text(),
+                (Token::Maek, Token::Keyword(Annotation::List | Annotation::Numbrd)) => {
+                    self.parse_list()
+                }
+                (Token::Maek, Token::Keyword(Annotation::Tabl)) => self.parse_table(),
+                (Token::Maek, Token::Keyword(Annotation::Kode)) => self.parse_code_block(),
+                (Token::Maek, Token::Keyword(Annotation::Kwote)) => self.parse_quote(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Heading)) => self.parse_heading(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Line)) => self.parse_rule(),
 
-            if self.current().starts_with("TEXT(") && self.current().ends_with(')') {
-                current = "TEXT()";
+                _ => break,
+            };
+            if let Err(e) = result {
+                self.recover(e, mark, closer);
             }
-            match (current, next_token) {
-                ("#LEMME SEE", _) => self.parse_variable_use(),
-                ("#GIMMEH", "BOLD") => self.parse_bold(),
-                ("#GIMMEH", "ITALICS") => self.parse_italics(),
-                ("#GIMMEH", "NEWLINE") => self.parse_newline(),
-                ("#GIMMEH", "SOUNDZ") => self.parse_audio(),
-                ("#GIMMEH", "VIDZ") => self.parse_video(),
-                ("TEXT()", _) => self.parse_inner_text(),
-                ("#MAEK", "LIST") => self.parse_list(),
+        }
+        Ok(())
+    }
+    //a run of words, with the whitespace between them as written
+    fn parse_inner_text(&mut self) -> Result<(), CompileError> {
+        self.parse_space();
+        let start = self.current_span();
+        let mut run = match self.current() {
+            Token::Text(txt) => txt.clone(),
+            _ => return Err(self.error("text")),
+        };
+        self.position += 1;
+        while let Some(Lexeme { token: Token::Text(txt), space, .. }) =
+            self.tokens.get(self.position)
+        {
+            run.push_str(space);
+            run.push_str(txt);
+            self.position += 1;
+        }
+        let span = start.to(self.previous_span());
+        let joined = matches!(self.previous_sibling(), Some(AST::Text(..)));
+        match self.ast.last_mut() {
+            Some(AST::Text(text, text_span)) if joined => {
+                text.push_str(&run);
+                *text_span = text_span.to(span);
+            }
+            _ => self.ast.push(AST::Text(run, span)),
+        }
+        Ok(())
+    }
+
+    fn parse_heading(&mut self) -> Result<(), CompileError> {
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Heading))?;
+        let level = match self.current() {
+            Token::Text(n) => n.parse::<u32>().ok(),
+            _ => None,
+        };
+        let Some(level) = level else {
+            return Err(self.error("a heading level"));
+        };
+        self.position += 1;
+
+        let start_len = self.ast.len();
+        self.parse_inline()?;
+        self.close(Token::Mkay)?;
+
+        let items = self.ast.drain(start_len..).collect();
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Heading { level, items, span });
+        Ok(())
+    }
 
+    //text, emphasis, variable uses, links and images, as in a heading or in bold
+    //text; the text of a link may hold anything but another link
+    fn parse_inline(&mut self) -> Result<(), CompileError> {
+        while self.position < self.tokens.len() {
+            let mark = self.checkpoint();
+            let closer = self.closer();
+            let result = match (self.current(), self.next()) {
+                (Token::Text(_), _) => self.parse_inner_text(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Bold)) => self.parse_bold(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Italics)) => self.parse_italics(),
+                (Token::LemmeSee, _) => self.parse_variable_use(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Link)) if !self.in_link => {
+                    self.parse_link()
+                }
+                (Token::Gimmeh, Token::Keyword(Annotation::Pikchur)) => self.parse_image(),
                 _ => break,
+            };
+            if let Err(e) = result {
+                self.recover(e, mark, closer);
             }
         }
-    }
-    fn parse_inner_text(&mut self) {
-        self.parse_text();
+        Ok(())
     }
 
-    fn parse_variable_define(&mut self) {
-        self.expect("#I HAZ");
-        self.parse_text();
-        self.expect("#IT IZ");
-        self.parse_text();
-        self.expect("#MKAY");
+    fn parse_variable_define(&mut self) -> Result<(), CompileError> {
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::IHaz)?;
+        self.parse_text()?;
+        self.expect(Token::ItIz)?;
+        self.parse_text()?;
+        self.close(Token::Mkay)?;
 
         let value_node = self.ast.pop().unwrap();
         let name_node = self.ast.pop().unwrap();
 
         let value = match value_node {
-            AST::Text(s) => s,
+            AST::Text(s, _) => s,
             _ => "<bad value>".to_string(),
         };
         let name = match name_node {
-            AST::Text(s) => s,
+            AST::Text(s, _) => s,
             _ => "<bad name>".to_string(),
         };
 
-        self.ast.push(AST::VarDefine { name, value });
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::VarDefine { name, value, span });
+        Ok(())
     }
 
-    fn parse_variable_use(&mut self) {
-        self.expect("#LEMME SEE");
-        self.parse_text();
-        self.expect("#MKAY");
+    fn parse_variable_use(&mut self) -> Result<(), CompileError> {
+        self.parse_space();
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::LemmeSee)?;
+        self.parse_text()?;
+        self.close(Token::Mkay)?;
 
         let name_node = self.ast.pop().unwrap();
         let name = match name_node {
-            AST::Text(s) => s,
+            AST::Text(s, _) => s,
             _ => "<bad var>".to_string(),
         };
-        self.ast.push(AST::VarUse(name));
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::VarUse {
+            name,
+            value: None,
+            span,
+        });
+        Ok(())
     }
 
-    fn parse_bold(&mut self) {
-        self.expect("#GIMMEH");
-        self.expect("BOLD");
+    fn parse_bold(&mut self) -> Result<(), CompileError> {
+        self.parse_space();
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Bold))?;
+        let start_len = self.ast.len();
+        self.parse_inline()?;
+        self.close(Token::Mkay)?;
 
-        let mut parts: Vec<String> = Vec::new();
-        while self.is_text(self.current()) {
-            let tok = self.current().to_string();
-            let txt = Self::text_content(&tok);
-            self.position += 1;
-            parts.push(txt);
-        }
+        let items = self.ast.drain(start_len..).collect();
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Bold(items, span));
+        Ok(())
+    }
 
-        self.expect("#MKAY");
+    fn parse_italics(&mut self) -> Result<(), CompileError> {
+        self.parse_space();
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Italics))?;
+        let start_len = self.ast.len();
+        self.parse_inline()?;
+        self.close(Token::Mkay)?;
 
-        let inner = parts.join(" ");
-        self.ast.push(AST::Bold(inner));
+        let items = self.ast.drain(start_len..).collect();
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Italics(items, span));
+        Ok(())
     }
 
-    fn parse_italics(&mut self) {
-        self.expect("#GIMMEH");
-        self.expect("ITALICS");
+    fn parse_list(&mut self) -> Result<(), CompileError> {
+        let start_len = self.ast.len();
+        let start = self.current_span();
 
-        let mut parts = Vec::new();
-        while self.is_text(self.current()) {
-            let tok = self.current().to_string();
-            let txt = Self::text_content(&tok);
+        self.open(Token::Oic);
+        self.expect(Token::Maek)?;
+        let ordered = *self.current() == Token::Keyword(Annotation::Numbrd);
+        if ordered {
+            self.position += 1;
+        }
+        self.expect(Token::Keyword(Annotation::List))?;
+        //`#MAEK NUMBRD LIST 3` counts from 3
+        let mut first = None;
+        if let (true, Token::Text(n)) = (ordered, self.current()) {
+            match n.parse::<u32>() {
+                Ok(n) => first = Some(n),
+                Err(_) => return Err(self.error("the number of the first item")),
+            }
             self.position += 1;
-            parts.push(txt);
         }
+        self.parse_list_items()?;
+        self.close(Token::Oic)?;
 
-        self.expect("#MKAY");
+        let items = self.ast.drain(start_len..).collect();
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::List {
+            ordered,
+            start: first,
+            items,
+            span,
+        });
+        Ok(())
+    }
 
-        let inner = parts.join(" ");
-        self.ast.push(AST::Italics(inner));
+    fn parse_list_items(&mut self) -> Result<(), CompileError> {
+        while *self.current() == Token::Gimmeh && *self.next() == Token::Keyword(Annotation::Item) {
+            let start_len = self.ast.len();
+            let start = self.current_span();
+            self.open(Token::Mkay);
+            self.expect(Token::Gimmeh)?;
+            self.expect(Token::Keyword(Annotation::Item))?;
+            self.parse_inner_list()?;
+            self.close(Token::Mkay)?;
+            let kids = self.ast.drain(start_len..).collect();
+            let span = start.to(self.previous_span());
+            self.ast.push(AST::ListItem { items: kids, span });
+        }
+        Ok(())
     }
 
-    fn parse_list(&mut self) {
+    fn parse_inner_list(&mut self) -> Result<(), CompileError> {
+        while self.position < self.tokens.len() {
+            let mark = self.checkpoint();
+            let closer = self.closer();
+            let result = match (self.current(), self.next()) {
+                (Token::Text(_), _) => self.parse_inner_text(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Bold)) => self.parse_bold(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Italics)) => self.parse_italics(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Link)) => self.parse_link(),
+                (Token::Gimmeh, Token::Keyword(Annotation::Pikchur)) => self.parse_image(),
+                (Token::LemmeSee, _) => self.parse_variable_use(),
+                (Token::Maek, Token::Keyword(Annotation::List | Annotation::Numbrd)) => {
+                    self.parse_list()
+                }
+                _ => break,
+            };
+            if let Err(e) = result {
+                self.recover(e, mark, closer);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_table(&mut self) -> Result<(), CompileError> {
         let start_len = self.ast.len();
+        let start = self.current_span();
 
-        self.expect("#MAEK");
-        self.expect("LIST");
-        self.parse_list_items();
-        self.expect("#OIC");
+        self.open(Token::Oic);
+        self.expect(Token::Maek)?;
+        self.expect(Token::Keyword(Annotation::Tabl))?;
+        self.parse_table_rows()?;
+        self.close(Token::Oic)?;
 
-        let items = self.ast.drain(start_len..).collect();
-        self.ast.push(AST::List { items });
+        let rows = self.ast.drain(start_len..).collect();
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Table { rows, span });
+        Ok(())
     }
 
-    fn parse_list_items(&mut self) {
-        while self.current() == "#GIMMEH" && self.tokens[self.position + 1].as_str() == "ITEM" {
+    fn parse_table_rows(&mut self) -> Result<(), CompileError> {
+        while let (Token::Gimmeh, Token::Keyword(Annotation::Row | Annotation::Hedr)) =
+            (self.current(), self.next())
+        {
             let start_len = self.ast.len();
-            self.expect("#GIMMEH");
-            self.expect("ITEM");
-            self.parse_inner_list();
-            self.expect("#MKAY");
-            let kids = self.ast.drain(start_len..).collect();
-            self.ast.push(AST::ListItem { items: kids });
+            let start = self.current_span();
+            self.open(Token::Mkay);
+            self.expect(Token::Gimmeh)?;
+            let header = *self.current() == Token::Keyword(Annotation::Hedr);
+            if header {
+                self.position += 1;
+            }
+            self.expect(Token::Keyword(Annotation::Row))?;
+            self.parse_table_cells()?;
+            self.close(Token::Mkay)?;
+            let cells = self.ast.drain(start_len..).collect();
+            let span = start.to(self.previous_span());
+            self.ast.push(AST::Row {
+                header,
+                cells,
+                span,
+            });
         }
+        Ok(())
     }
 
-    fn parse_inner_list(&mut self) {
-        while self.position < self.tokens.len() {
-            let cur = self.current();
-            let next_token = if self.position + 1 < self.tokens.len() {
-                self.tokens[self.position + 1].as_str()
-            } else {
-                ""
-            };
+    fn parse_table_cells(&mut self) -> Result<(), CompileError> {
+        while *self.current() == Token::Gimmeh && *self.next() == Token::Keyword(Annotation::Cell) {
+            let start_len = self.ast.len();
+            let start = self.current_span();
+            self.open(Token::Mkay);
+            self.expect(Token::Gimmeh)?;
+            self.expect(Token::Keyword(Annotation::Cell))?;
+            self.parse_inline()?;
+            self.close(Token::Mkay)?;
+            let items = self.ast.drain(start_len..).collect();
+            let span = start.to(self.previous_span());
+            self.ast.push(AST::Cell { items, span });
+        }
+        Ok(())
+    }
+
+    fn parse_code_block(&mut self) -> Result<(), CompileError> {
+        let start = self.current_span();
+        self.open(Token::Oic);
+        self.expect(Token::Maek)?;
+        self.expect(Token::Keyword(Annotation::Kode))?;
+        //the lexer gives the language, if any, then the code as one token
+        let mut lang = None;
+        if let Token::Text(l) = self.current() {
+            lang = Some(l.clone());
+            self.position += 1;
+        }
+        let mut text = String::new();
+        if let Token::Verbatim(code) = self.current() {
+            text = code.clone();
+            self.position += 1;
+        }
+        self.close(Token::Oic)?;
 
-            match (cur, next_token) {
-                (t, _) if self.is_text(t) => self.parse_text(),
-                ("#GIMMEH", "BOLD") => self.parse_bold(),
-                ("#GIMMEH", "ITALICS") => self.parse_italics(),
-                ("#LEMME SEE", _) => self.parse_variable_use(),
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::CodeBlock { lang, text, span });
+        Ok(())
+    }
+
+    //paragraphs and anything a paragraph may hold, then the attribution
+    fn parse_quote(&mut self) -> Result<(), CompileError> {
+        let start_len = self.ast.len();
+        let start = self.current_span();
+        self.open(Token::Oic);
+        self.expect(Token::Maek)?;
+        self.expect(Token::Keyword(Annotation::Kwote))?;
+        loop {
+            self.parse_inner_paragraph()?;
+            let mark = self.checkpoint();
+            let result = match (self.current(), self.next()) {
+                (Token::Maek, Token::Keyword(Annotation::Paragraf)) => self.parse_paragraph(),
                 _ => break,
+            };
+            if let Err(e) = result {
+                self.recover(e, mark, Token::Oic);
             }
         }
+        let items = self.ast.drain(start_len..).collect();
+
+        if (self.current(), self.next()) == (&Token::Gimmeh, &Token::Keyword(Annotation::Sez)) {
+            self.open(Token::Mkay);
+            self.expect(Token::Gimmeh)?;
+            self.expect(Token::Keyword(Annotation::Sez))?;
+            self.parse_inline()?;
+            self.close(Token::Mkay)?;
+        }
+        let cite = self.ast.drain(start_len..).collect();
+        self.close(Token::Oic)?;
+
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Quote { items, cite, span });
+        Ok(())
+    }
+
+    fn parse_rule(&mut self) -> Result<(), CompileError> {
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Line))?;
+        self.close(Token::Mkay)?;
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Rule(span));
+        Ok(())
     }
 
-    fn parse_audio(&mut self) {
-        self.expect("#GIMMEH");
-        self.expect("SOUNDZ");
-        self.parse_text();
-        self.expect("#MKAY");
+    fn parse_audio(&mut self) -> Result<(), CompileError> {
+        self.parse_space();
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Soundz))?;
+        self.parse_text()?;
+        self.close(Token::Mkay)?;
 
         let url_node = self.ast.pop().unwrap();
         let url = match url_node {
-            AST::Text(s) => s,
+            AST::Text(s, _) => s,
             _ => "".to_string(),
         };
-        self.ast.push(AST::Audio(url));
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Audio(url, span));
+        Ok(())
     }
 
-    fn parse_video(&mut self) {
-        self.expect("#GIMMEH");
-        self.expect("VIDZ");
-        self.parse_text();
-        self.expect("#MKAY");
+    fn parse_video(&mut self) -> Result<(), CompileError> {
+        self.parse_space();
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Vidz))?;
+        self.parse_text()?;
+        self.close(Token::Mkay)?;
 
         let url_node = self.ast.pop().unwrap();
         let url = match url_node {
-            AST::Text(s) => s,
+            AST::Text(s, _) => s,
+            _ => "".to_string(),
+        };
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Video(url, span));
+        Ok(())
+    }
+
+    fn parse_link(&mut self) -> Result<(), CompileError> {
+        self.parse_space();
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Link))?;
+        self.parse_text()?;
+        let url = match self.ast.pop() {
+            Some(AST::Text(s, _)) => s,
+            _ => "".to_string(),
+        };
+
+        let start_len = self.ast.len();
+        let outer = std::mem::replace(&mut self.in_link, true);
+        let inner = self.parse_inline();
+        self.in_link = outer;
+        inner?;
+        self.close(Token::Mkay)?;
+
+        let items = self.ast.drain(start_len..).collect();
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Link { url, items, span });
+        Ok(())
+    }
+
+    fn parse_image(&mut self) -> Result<(), CompileError> {
+        self.parse_space();
+        let start = self.current_span();
+        self.open(Token::Mkay);
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Pikchur))?;
+        self.parse_text()?;
+        let url = match self.ast.pop() {
+            Some(AST::Text(s, _)) => s,
             _ => "".to_string(),
         };
-        self.ast.push(AST::Video(url));
+
+        //every word after the url is the alt text
+        let mut parts: Vec<String> = Vec::new();
+        while let Token::Text(txt) = self.current() {
+            parts.push(txt.clone());
+            self.position += 1;
+        }
+
+        self.close(Token::Mkay)?;
+
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Image {
+            url,
+            alt: parts.join(" "),
+            span,
+        });
+        Ok(())
     }
 
-    fn parse_newline(&mut self) {
-        self.expect("#GIMMEH");
-        self.expect("NEWLINE");
-        self.ast.push(AST::Newline);
+    fn parse_newline(&mut self) -> Result<(), CompileError> {
+        let start = self.current_span();
+        self.expect(Token::Gimmeh)?;
+        self.expect(Token::Keyword(Annotation::Newline))?;
+        let span = start.to(self.previous_span());
+        self.ast.push(AST::Newline(span));
+        Ok(())
     }
 
-    fn parse_text(&mut self) {
-        let tok = self.current();
-        if tok.starts_with("TEXT(") && tok.ends_with(')') {
-            let txt = Self::text_content(tok);
+    fn parse_text(&mut self) -> Result<(), CompileError> {
+        if let Token::Text(txt) = self.current() {
+            let txt = txt.clone();
+            let span = self.current_span();
             self.position += 1;
-            self.ast.push(AST::Text(txt));
+            self.ast.push(AST::Text(txt, span));
+            Ok(())
         } else {
-            self.error("TEXT()", "parse_text");
+            Err(self.error("text"))
         }
     }
 }
+
+//content that runs on within a line, so whitespace next to it is kept
+fn is_inline(node: &AST) -> bool {
+    matches!(
+        node,
+        AST::Text(..)
+            | AST::Bold(..)
+            | AST::Italics(..)
+            | AST::Link { .. }
+            | AST::Image { .. }
+            | AST::VarUse { .. }
+            | AST::Audio(..)
+            | AST::Video(..)
+    )
+}
@@ -0,0 +1,151 @@
+use std::fmt;
+
+/// Plain annotation words that follow `#MAEK` or `#GIMMEH`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Annotation {
+    Head,
+    Title,
+    Paragraf,
+    Bold,
+    Italics,
+    List,
+    Item,
+    Newline,
+    Soundz,
+    Vidz,
+    Style,
+    Author,
+    Description,
+    Keywords,
+    Language,
+    Favicon,
+    Heading,
+    Link,
+    Pikchur,
+    Numbrd,
+    Tabl,
+    Row,
+    Cell,
+    Hedr,
+    Kode,
+    Kwote,
+    Sez,
+    Line,
+}
+
+impl Annotation {
+    /// Whether the annotation may appear as `#GIMMEH <annotation> ... #MKAY`
+    /// inside `#MAEK HEAD`.
+    pub fn is_head_field(&self) -> bool {
+        matches!(
+            self,
+            Annotation::Title
+                | Annotation::Style
+                | Annotation::Author
+                | Annotation::Description
+                | Annotation::Keywords
+                | Annotation::Language
+                | Annotation::Favicon
+        )
+    }
+
+    /// The word as it is written in LOLCODE source.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Annotation::Head => "HEAD",
+            Annotation::Title => "TITLE",
+            Annotation::Paragraf => "PARAGRAF",
+            Annotation::Bold => "BOLD",
+            Annotation::Italics => "ITALICS",
+            Annotation::List => "LIST",
+            Annotation::Item => "ITEM",
+            Annotation::Newline => "NEWLINE",
+            Annotation::Soundz => "SOUNDZ",
+            Annotation::Vidz => "VIDZ",
+            Annotation::Style => "STYLE",
+            Annotation::Author => "AUTHOR",
+            Annotation::Description => "DESCRIPTION",
+            Annotation::Keywords => "KEYWORDS",
+            Annotation::Language => "LANGUAGE",
+            Annotation::Favicon => "FAVICON",
+            Annotation::Heading => "HEADING",
+            Annotation::Link => "LINK",
+            Annotation::Pikchur => "PIKCHUR",
+            Annotation::Numbrd => "NUMBRD",
+            Annotation::Tabl => "TABL",
+            Annotation::Row => "ROW",
+            Annotation::Cell => "CELL",
+            Annotation::Hedr => "HEDR",
+            Annotation::Kode => "KODE",
+            Annotation::Kwote => "KWOTE",
+            Annotation::Sez => "SEZ",
+            Annotation::Line => "LINE",
+        }
+    }
+}
+
+/// A token produced by `LolLexer::next_token`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    Hai,
+    KthxBye,
+    Obtw,
+    Tldr,
+    Maek,
+    Oic,
+    Gimmeh,
+    Mkay,
+    IHaz,
+    ItIz,
+    LemmeSee,
+    Keyword(Annotation),
+    Text(String),
+    /// The contents of a `#MAEK KODE` block, exactly as written.
+    Verbatim(String),
+    Eof,
+}
+
+impl Token {
+    /// The variant name, used as the `kind` of a token in JSON output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Token::Hai => "Hai",
+            Token::KthxBye => "KthxBye",
+            Token::Obtw => "Obtw",
+            Token::Tldr => "Tldr",
+            Token::Maek => "Maek",
+            Token::Oic => "Oic",
+            Token::Gimmeh => "Gimmeh",
+            Token::Mkay => "Mkay",
+            Token::IHaz => "IHaz",
+            Token::ItIz => "ItIz",
+            Token::LemmeSee => "LemmeSee",
+            Token::Keyword(_) => "Keyword",
+            Token::Text(_) => "Text",
+            Token::Verbatim(_) => "Verbatim",
+            Token::Eof => "Eof",
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    //tokens are shown the way they are written in the source, for error messages
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Hai => write!(f, "#HAI"),
+            Token::KthxBye => write!(f, "#KTHXBYE"),
+            Token::Obtw => write!(f, "#OBTW"),
+            Token::Tldr => write!(f, "#TLDR"),
+            Token::Maek => write!(f, "#MAEK"),
+            Token::Oic => write!(f, "#OIC"),
+            Token::Gimmeh => write!(f, "#GIMMEH"),
+            Token::Mkay => write!(f, "#MKAY"),
+            Token::IHaz => write!(f, "#I HAZ"),
+            Token::ItIz => write!(f, "#IT IZ"),
+            Token::LemmeSee => write!(f, "#LEMME SEE"),
+            Token::Keyword(a) => write!(f, "{}", a.as_str()),
+            Token::Text(s) | Token::Verbatim(s) => write!(f, "{}", s),
+            Token::Eof => write!(f, "end of file"),
+        }
+    }
+}
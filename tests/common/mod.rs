@@ -0,0 +1,11 @@
+//! Helpers shared by the integration tests.
+
+use lolcode_compiler::{Options, compile_str};
+
+/// Compiles `source`, failing the test on any diagnostic.
+pub fn compile(source: &str, options: Options) -> String {
+    match compile_str(source, &options) {
+        Ok(output) => output,
+        Err(diagnostics) => panic!("failed to compile: {:?}", diagnostics),
+    }
+}
@@ -0,0 +1,119 @@
+//! Checks that one run reports every error it can find, from all the phases.
+
+use lolcode_compiler::{Diagnostic, Options, Token, compile_str, parse, scan, tokenize};
+
+/// Has a bad annotation on lines 3 and 8, a `#GIMMEH BOLD` left open across
+/// the `#OIC` on line 5, an undefined variable on line 7, a heading level out
+/// of range on line 9, and a head after the body on line 11.
+const MANY_ERRORS: &str = "#HAI
+#MAEK PARAGRAF
+one #FOO two
+#GIMMEH BOLD unclosed
+#OIC
+#MAEK PARAGRAF
+#LEMME SEE nobody #MKAY
+#GIMMEH ITALICS fine #MKAY #ZORK
+#GIMMEH HEADING 9 too deep #MKAY
+#OIC
+#MAEK HEAD #GIMMEH TITLE late #MKAY #OIC
+#KTHXBYE
+";
+
+//a paragraph holding one `#GIMMEH tag url #MKAY`
+fn with_url(tag: &str, url: &str) -> String {
+    format!("#HAI\n#MAEK PARAGRAF #GIMMEH {} {} #MKAY #OIC\n#KTHXBYE\n", tag, url)
+}
+
+fn codes_and_lines(diagnostics: &[Diagnostic]) -> Vec<(&'static str, Option<usize>)> {
+    diagnostics
+        .iter()
+        .map(|d| (d.error.code(), d.error.span().map(|s| s.line)))
+        .collect()
+}
+
+#[test]
+fn compile_reports_lexical_syntax_and_semantic_errors_together() {
+    let diagnostics = compile_str(MANY_ERRORS, &Options::default()).unwrap_err();
+    assert_eq!(
+        codes_and_lines(&diagnostics),
+        [
+            ("E0001", Some(3)),
+            ("E0001", Some(8)),
+            ("E0102", Some(5)),
+            ("E0101", Some(11)),
+            ("E0201", Some(7)),
+            ("E0202", Some(9)),
+        ]
+    );
+}
+
+#[test]
+fn scan_skips_bad_annotations_and_keeps_the_rest() {
+    let (tokens, diagnostics) = scan(MANY_ERRORS);
+    assert_eq!(codes_and_lines(&diagnostics), [("E0001", Some(3)), ("E0001", Some(8))]);
+    assert_eq!(tokens.last().map(|t| &t.token), Some(&Token::Eof));
+    assert!(tokens.iter().any(|t| t.token == Token::Text("two".to_string())));
+
+    assert_eq!(tokenize(MANY_ERRORS).unwrap_err(), diagnostics);
+}
+
+#[test]
+fn parse_recovers_after_an_unclosed_tag() {
+    let (tokens, _) = scan(MANY_ERRORS);
+    let diagnostics = parse(&tokens).unwrap_err();
+    assert_eq!(codes_and_lines(&diagnostics), [("E0102", Some(5)), ("E0101", Some(11))]);
+}
+
+#[test]
+fn block_after_a_bad_maek_is_still_checked() {
+    let source = "#HAI
+#MAEK BOGUS stuff #OIC
+#MAEK PARAGRAF #LEMME SEE nope #MKAY #GIMMEH BOLD x #OIC
+#OBTW fine #TLDR
+#MAEK BOGUS #MAEK LIST #GIMMEH ITEM a #MKAY #OIC #OIC
+#MAEK PARAGRAF #GIMMEH ITALICS y #OIC
+#KTHXBYE
+";
+    let diagnostics = compile_str(source, &Options::default()).unwrap_err();
+    assert_eq!(
+        codes_and_lines(&diagnostics),
+        [
+            ("E0101", Some(2)),
+            ("E0102", Some(3)),
+            ("E0101", Some(5)),
+            ("E0102", Some(6)),
+            ("E0201", Some(3)),
+        ]
+    );
+}
+
+#[test]
+fn urls_that_can_run_code_are_refused() {
+    for url in [
+        "javascript:alert(1)",
+        "JavaScript:alert(1)",
+        " java\u{1}script:alert(1)",
+        "vbscript:msgbox",
+        "data:text/html,<script>alert(1)</script>",
+    ] {
+        for tag in ["LINK", "PIKCHUR", "SOUNDZ", "VIDZ"] {
+            let source = with_url(tag, url);
+            let diagnostics = compile_str(&source, &Options::default()).unwrap_err();
+            assert_eq!(codes_and_lines(&diagnostics), [("E0204", Some(2))], "{}", source);
+        }
+    }
+}
+
+#[test]
+fn web_relative_and_image_data_urls_are_allowed() {
+    for (tag, url) in [
+        ("LINK", "https://example.com/a:b"),
+        ("LINK", "mailto:cat@example.com"),
+        ("LINK", "notes.html"),
+        ("PIKCHUR", "data:image/png;base64,iVBORw0KGgo="),
+        ("VIDZ", "//example.com/v"),
+    ] {
+        let source = with_url(tag, url);
+        assert!(compile_str(&source, &Options::default()).is_ok(), "{}", source);
+    }
+}
@@ -0,0 +1,584 @@
+//! Checks the HTML backend against html5ever, standing in for an HTML5
+//! conformance checker: the output must parse in no-quirks mode without a
+//! single parse error, and have the document structure HTML5 asks for.
+
+use std::path::PathBuf;
+use std::{env, fs, process};
+
+use html5ever::tendril::TendrilSink;
+use html5ever::tree_builder::{QuirksMode, TreeBuilderOpts};
+use html5ever::{ParseOpts, parse_document};
+use lolcode_compiler::{HtmlStyle, Options, Theme, compile_str};
+use markup5ever_rcdom::{Handle, NodeData, RcDom};
+
+mod common;
+
+const FULL: &str = "#HAI
+#OBTW a comment -- with --> dashes #TLDR
+#MAEK HEAD #GIMMEH TITLE My Page #MKAY #OIC
+#MAEK PARAGRAF
+#I HAZ name #IT IZ Bob #MKAY
+Hello #LEMME SEE name #MKAY , this is #GIMMEH BOLD strong words #MKAY and #GIMMEH ITALICS it #MKAY .
+#GIMMEH NEWLINE
+#MAEK LIST #GIMMEH ITEM one #MKAY #GIMMEH ITEM two #GIMMEH BOLD b #MKAY #MKAY #OIC
+after the items
+#OIC
+#GIMMEH SOUNDZ http://a.mp3 #MKAY
+#GIMMEH VIDZ http://v?a=1&b=\"2\" #MKAY
+#KTHXBYE
+";
+
+fn compile(source: &str) -> String {
+    compile_styled(source, HtmlStyle::default())
+}
+
+fn compile_styled(source: &str, html_style: HtmlStyle) -> String {
+    common::compile(
+        source,
+        Options {
+            html_style,
+            ..Options::default()
+        },
+    )
+}
+
+/// Parses `html` and fails the test on any parse error or quirks mode.
+fn conforming(html: &str) -> RcDom {
+    let opts = ParseOpts {
+        tree_builder: TreeBuilderOpts {
+            exact_errors: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let dom = parse_document(RcDom::default(), opts).one(html);
+    assert!(
+        dom.errors.is_empty(),
+        "parse errors {:?} in:\n{}",
+        dom.errors,
+        html
+    );
+    assert_eq!(dom.quirks_mode, QuirksMode::NoQuirks, "in:\n{}", html);
+    dom
+}
+
+fn name(node: &Handle) -> Option<String> {
+    match &node.data {
+        NodeData::Element { name, .. } => Some(name.local.to_string()),
+        _ => None,
+    }
+}
+
+fn attr(node: &Handle, wanted: &str) -> Option<String> {
+    match &node.data {
+        NodeData::Element { attrs, .. } => attrs
+            .borrow()
+            .iter()
+            .find(|a| &*a.name.local == wanted)
+            .map(|a| a.value.to_string()),
+        _ => None,
+    }
+}
+
+fn elements(node: &Handle) -> Vec<Handle> {
+    node.children
+        .borrow()
+        .iter()
+        .filter(|c| name(c).is_some())
+        .cloned()
+        .collect()
+}
+
+fn child(node: &Handle, wanted: &str) -> Handle {
+    elements(node)
+        .into_iter()
+        .find(|c| name(c).as_deref() == Some(wanted))
+        .unwrap_or_else(|| panic!("no <{}> element", wanted))
+}
+
+fn text(node: &Handle) -> String {
+    let mut out = String::new();
+    for c in node.children.borrow().iter() {
+        match &c.data {
+            NodeData::Text { contents } => out.push_str(&contents.borrow()),
+            NodeData::Element { .. } => out.push_str(&text(c)),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Checks the parts every generated document must have and returns the
+/// `<head>` and `<body>`, along with the tree that owns them: dropping an
+/// `RcDom` detaches every node's children.
+fn document(html: &str) -> (RcDom, Handle, Handle) {
+    assert!(html.starts_with("<!DOCTYPE html>"), "no doctype in:\n{}", html);
+    let dom = conforming(html);
+
+    let root = elements(&dom.document);
+    assert_eq!(root.len(), 1);
+    let html_el = root[0].clone();
+    assert_eq!(name(&html_el).as_deref(), Some("html"));
+    assert_eq!(attr(&html_el, "lang").as_deref(), Some("en"));
+
+    let names: Vec<String> = elements(&html_el).iter().filter_map(name).collect();
+    assert_eq!(names, ["head", "body"]);
+    let head = child(&html_el, "head");
+    let body = child(&html_el, "body");
+
+    let metas: Vec<Handle> = elements(&head)
+        .into_iter()
+        .filter(|e| name(e).as_deref() == Some("meta"))
+        .collect();
+    assert!(metas.iter().any(|m| attr(m, "charset").as_deref() == Some("utf-8")));
+    assert!(metas.iter().any(|m| {
+        attr(m, "name").as_deref() == Some("viewport") && attr(m, "content").is_some()
+    }));
+    assert!(!text(&child(&head, "title")).trim().is_empty());
+    (dom, head, body)
+}
+
+#[test]
+fn full_document_is_valid_html5() {
+    let (_dom, head, body) = document(&compile(FULL));
+    assert_eq!(text(&child(&head, "title")), "My Page");
+
+    let names: Vec<String> = elements(&body).iter().filter_map(name).collect();
+    assert_eq!(names, ["p", "ul", "p", "audio", "iframe"]);
+    assert_eq!(
+        attr(&child(&body, "iframe"), "src").as_deref(),
+        Some("http://v?a=1&b=\"2\"")
+    );
+}
+
+#[test]
+fn head_is_written_when_the_source_has_none() {
+    let html = compile("#HAI\nhello #GIMMEH BOLD there #MKAY\n#KTHXBYE\n");
+    let (_dom, head, body) = document(&html);
+    assert_eq!(text(&child(&head, "title")), "Untitled");
+    assert_eq!(text(&child(&body, "b")), "there");
+}
+
+#[test]
+fn head_metadata_becomes_meta_and_link_tags() {
+    let html = compile(
+        "#HAI
+#MAEK HEAD
+#GIMMEH DESCRIPTION All about \"cats\" #MKAY
+#GIMMEH TITLE Cats #MKAY
+#GIMMEH AUTHOR Ceiling Cat #MKAY
+#GIMMEH KEYWORDS cats, lol #MKAY
+#GIMMEH KEYWORDS memes #MKAY
+#GIMMEH FAVICON icon.png #MKAY
+#OIC
+#KTHXBYE
+",
+    );
+    let (_dom, head, _) = document(&html);
+    let content = |key: &str, wanted: &str| {
+        elements(&head)
+            .into_iter()
+            .find(|e| attr(e, key).as_deref() == Some(wanted))
+            .and_then(|e| attr(&e, "content"))
+    };
+    assert_eq!(content("name", "description").as_deref(), Some("All about \"cats\""));
+    assert_eq!(content("name", "author").as_deref(), Some("Ceiling Cat"));
+    assert_eq!(content("name", "keywords").as_deref(), Some("cats, lol, memes"));
+    assert_eq!(content("property", "og:title").as_deref(), Some("Cats"));
+    assert_eq!(
+        content("property", "og:description").as_deref(),
+        Some("All about \"cats\"")
+    );
+    let icon = elements(&head)
+        .into_iter()
+        .find(|e| attr(e, "rel").as_deref() == Some("icon"))
+        .expect("no icon link");
+    assert_eq!(attr(&icon, "href").as_deref(), Some("icon.png"));
+
+    let html = compile("#HAI\n#MAEK HEAD #GIMMEH LANGUAGE fr #MKAY #OIC\n#KTHXBYE\n");
+    let dom = conforming(&html);
+    assert_eq!(attr(&elements(&dom.document)[0], "lang").as_deref(), Some("fr"));
+}
+
+#[test]
+fn bold_and_italics_nest() {
+    let html = compile(
+        "#HAI
+#I HAZ name #IT IZ Bob #MKAY
+#MAEK PARAGRAF
+#GIMMEH BOLD hi #GIMMEH ITALICS #LEMME SEE name #MKAY #MKAY #MKAY
+#GIMMEH ITALICS see #GIMMEH LINK http://x here #MKAY #MKAY
+#OIC
+#KTHXBYE
+",
+    );
+    let (_dom, _, body) = document(&html);
+    let p = child(&body, "p");
+    let i = child(&child(&p, "b"), "i");
+    assert_eq!(text(&i), "Bob");
+    let a = child(&child(&p, "i"), "a");
+    assert_eq!(attr(&a, "href").as_deref(), Some("http://x"));
+    assert_eq!(text(&a).trim(), "here");
+}
+
+#[test]
+fn punctuation_next_to_inline_tags_keeps_its_spacing() {
+    let source = "#HAI
+#MAEK PARAGRAF
+#I HAZ name #IT IZ Bob #MKAY
+Hello #LEMME SEE name #MKAY, this is #GIMMEH BOLD strong words#MKAY and #GIMMEH ITALICS it #MKAY.
+(#GIMMEH LINK http://x here#MKAY) \"#GIMMEH BOLD #GIMMEH ITALICS q #MKAY#MKAY\"
+#OIC
+#KTHXBYE
+";
+    for style in [HtmlStyle::Plain, HtmlStyle::Pretty, HtmlStyle::Minified] {
+        let html = compile_styled(source, style);
+        let (_dom, _, body) = document(&html);
+        let p = child(&body, "p");
+        let words = text(&p).split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_eq!(words, "Hello Bob, this is strong words and it. (here) \"q\"");
+        assert!(html.contains("Bob, this is <b>strong words</b> and <i>it</i>."), "in:\n{}", html);
+        assert!(html.contains("(<a href=\"http://x\">here</a>) \"<b><i>q</i></b>\""));
+    }
+}
+
+#[test]
+fn table_in_a_paragraph_is_valid_html5() {
+    let html = compile(
+        "#HAI
+#MAEK PARAGRAF
+pets:
+#MAEK TABL
+#GIMMEH HEDR ROW #GIMMEH CELL name #MKAY #GIMMEH CELL age #MKAY #MKAY
+#GIMMEH ROW #GIMMEH CELL Bob #MKAY #GIMMEH CELL #GIMMEH BOLD 3 #MKAY #MKAY #MKAY
+#OIC
+that is all
+#OIC
+#KTHXBYE
+",
+    );
+    let (_dom, _, body) = document(&html);
+    let names: Vec<String> = elements(&body).iter().filter_map(name).collect();
+    assert_eq!(names, ["p", "table", "p"]);
+    let table = child(&body, "table");
+    let header = child(&child(&table, "thead"), "tr");
+    let cells: Vec<String> = elements(&header).iter().map(|c| text(c).trim().to_string()).collect();
+    assert_eq!(cells, ["name", "age"]);
+    let row = child(&child(&table, "tbody"), "tr");
+    assert_eq!(elements(&row).iter().filter_map(name).collect::<Vec<_>>(), ["td", "td"]);
+}
+
+#[test]
+fn quote_and_rule_are_valid_html5() {
+    let html = compile(
+        "#HAI
+#MAEK PARAGRAF
+before
+#MAEK KWOTE
+I can has cheezburger
+#MAEK PARAGRAF second #OIC
+#GIMMEH SEZ Happy #GIMMEH ITALICS Cat #MKAY #MKAY
+#OIC
+#GIMMEH LINE #MKAY
+after
+#OIC
+#KTHXBYE
+",
+    );
+    let (_dom, _, body) = document(&html);
+    let names: Vec<String> = elements(&body).iter().filter_map(name).collect();
+    assert_eq!(names, ["p", "blockquote", "hr", "p"]);
+    let quote = child(&body, "blockquote");
+    let names: Vec<String> = elements(&quote).iter().filter_map(name).collect();
+    assert_eq!(names, ["p", "p", "footer"]);
+    assert_eq!(text(&child(&child(&quote, "footer"), "cite")), "Happy Cat");
+}
+
+#[test]
+fn code_keeps_its_whitespace_in_every_style() {
+    let source = "#HAI
+#MAEK KODE rust
+fn main() {
+    if a < b && c {  }
+
+\tx(); // #MKAY
+}
+#OIC
+#KTHXBYE
+";
+    let code = "fn main() {\n    if a < b && c {  }\n\n\tx(); // #MKAY\n}";
+    for style in [HtmlStyle::Plain, HtmlStyle::Pretty, HtmlStyle::Minified] {
+        let (_dom, _, body) = document(&compile_styled(source, style));
+        let code_el = child(&child(&body, "pre"), "code");
+        assert_eq!(attr(&code_el, "class").as_deref(), Some("language-rust"));
+        assert_eq!(text(&code_el), code);
+    }
+}
+
+#[test]
+fn empty_document_is_valid_html5() {
+    let (_dom, _, body) = document(&compile("#HAI\n#KTHXBYE\n"));
+    assert!(elements(&body).is_empty());
+}
+
+#[test]
+fn markup_in_text_stays_text() {
+    let html = compile(
+        "#HAI
+#MAEK HEAD #GIMMEH TITLE a <title> & more #MKAY #OIC
+#MAEK PARAGRAF <script>x</script> #OIC
+#KTHXBYE
+",
+    );
+    let (_dom, head, body) = document(&html);
+    assert_eq!(text(&child(&head, "title")), "a <title> & more");
+    let p = child(&body, "p");
+    assert!(elements(&p).is_empty());
+    assert_eq!(text(&p).trim(), "<script>x</script>");
+}
+
+#[test]
+fn every_style_is_valid_and_renders_the_same_text() {
+    //whitespace collapses when rendered and blocks start new lines, so
+    //compare the words and where the lines break
+    fn words(node: &Handle, out: &mut String) {
+        for c in node.children.borrow().iter() {
+            match &c.data {
+                NodeData::Text { contents } => out.push_str(&contents.borrow()),
+                NodeData::Element { .. } => {
+                    let block = matches!(name(c).as_deref(), Some("p" | "ul" | "li" | "br"));
+                    if block {
+                        out.push_str(" | ");
+                    }
+                    words(c, out);
+                    if block {
+                        out.push_str(" | ");
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    let rendered = |style| {
+        let (_dom, _, body) = document(&compile_styled(FULL, style));
+        let mut out = String::new();
+        words(&body, &mut out);
+        out.split_whitespace().collect::<Vec<_>>().join(" ")
+    };
+    let plain = rendered(HtmlStyle::Plain);
+    assert_eq!(rendered(HtmlStyle::Pretty), plain);
+    assert_eq!(rendered(HtmlStyle::Minified), plain);
+
+    let minified = compile_styled(FULL, HtmlStyle::Minified);
+    assert!(!minified.contains('\n'));
+    assert!(!minified.contains("<!--"));
+}
+
+/// A fresh directory for the stylesheets a test inlines.
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("lolcode-{}-{}", test, process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// The stylesheets in `head`, in order: a `<style>`'s CSS, or a link's href.
+fn stylesheets(head: &Handle) -> Vec<String> {
+    elements(head)
+        .iter()
+        .filter_map(|e| match name(e).as_deref() {
+            Some("style") => Some(text(e)),
+            Some("link") if attr(e, "rel").as_deref() == Some("stylesheet") => attr(e, "href"),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn style_names_a_built_in_theme_or_links_a_file() {
+    let html = compile(
+        "#HAI
+#MAEK HEAD #GIMMEH STYLE dark #MKAY #GIMMEH STYLE darker.css #MKAY #OIC
+#KTHXBYE
+",
+    );
+    let (_dom, head, _) = document(&html);
+    let sheets = stylesheets(&head);
+    assert_eq!(sheets.len(), 2);
+    assert_eq!(sheets[0].trim(), Theme::Dark.css().trim());
+    assert_eq!(sheets[1], "darker.css");
+}
+
+#[test]
+fn stylesheets_come_theme_then_source_then_css_option() {
+    let options = Options {
+        theme: Some(Theme::Classic),
+        css: vec!["b.css".to_string(), "http://x/c.css".to_string()],
+        ..Options::default()
+    };
+    let html = common::compile(
+        "#HAI\n#MAEK HEAD #GIMMEH STYLE a.css #MKAY #GIMMEH STYLE lolcat #MKAY #OIC\n#KTHXBYE\n",
+        options,
+    );
+    let (_dom, head, _) = document(&html);
+    let sheets: Vec<String> = stylesheets(&head).iter().map(|s| s.trim().to_string()).collect();
+    assert_eq!(
+        sheets,
+        [
+            Theme::Classic.css().trim(),
+            "a.css",
+            Theme::Lolcat.css().trim(),
+            "b.css",
+            "http://x/c.css",
+        ]
+    );
+}
+
+#[test]
+fn inline_css_reads_style_paths_from_the_source_dir() {
+    let dir = temp_dir("inline-css");
+    fs::create_dir_all(dir.join("css")).unwrap();
+    fs::write(dir.join("css/page.css"), "p { color: red; }\n").unwrap();
+    let options = Options {
+        inline_css: true,
+        source_dir: Some(dir.clone()),
+        css: vec![dir.join("css/page.css").display().to_string()],
+        ..Options::default()
+    };
+    let html = common::compile(
+        "#HAI
+#MAEK HEAD
+#GIMMEH STYLE css/page.css #MKAY
+#GIMMEH STYLE https://cdn.example/remote.css #MKAY
+#OIC
+#KTHXBYE
+",
+        options,
+    );
+    let (_dom, head, _) = document(&html);
+    let sheets: Vec<String> = stylesheets(&head).iter().map(|s| s.trim().to_string()).collect();
+    assert_eq!(
+        sheets,
+        ["p { color: red; }", "https://cdn.example/remote.css", "p { color: red; }"]
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn missing_inline_stylesheet_is_an_io_error() {
+    let options = Options {
+        inline_css: true,
+        source_dir: Some(temp_dir("missing-css")),
+        ..Options::default()
+    };
+    let source = "#HAI\n#MAEK HEAD #GIMMEH STYLE nope.css #MKAY #OIC\n#KTHXBYE\n";
+    let diagnostics = compile_str(source, &options).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].error.code(), "E0301");
+    assert!(diagnostics[0].error.message().contains("nope.css"), "{}", diagnostics[0]);
+}
+
+#[test]
+fn inline_css_refuses_paths_outside_the_source_dir() {
+    let dir = temp_dir("outside-css");
+    fs::create_dir_all(dir.join("page")).unwrap();
+    fs::write(dir.join("secret.css"), "p { color: red; }\n").unwrap();
+    let secret = dir.join("secret.css").display().to_string();
+    for href in ["../secret.css", "css/../../secret.css", secret.as_str()] {
+        let options = Options {
+            inline_css: true,
+            source_dir: Some(dir.join("page")),
+            ..Options::default()
+        };
+        let source = format!("#HAI\n#MAEK HEAD #GIMMEH STYLE {} #MKAY #OIC\n#KTHXBYE\n", href);
+        let diagnostics = compile_str(&source, &options).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].error.code(), "E0301");
+        let message = diagnostics[0].error.message();
+        assert!(message.contains("outside the source's directory"), "{}", message);
+        assert!(!message.contains("color"), "{}", message);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn inlined_css_cannot_close_its_style_element() {
+    let dir = temp_dir("style-end-tag");
+    let css = "p::after { content: \"</style><script>x()</script>\"; }\n/* </STYLE > */\n";
+    fs::write(dir.join("evil.css"), css).unwrap();
+    for html_style in [HtmlStyle::Plain, HtmlStyle::Pretty, HtmlStyle::Minified] {
+        let options = Options {
+            html_style,
+            inline_css: true,
+            source_dir: Some(dir.clone()),
+            ..Options::default()
+        };
+        let html = common::compile(
+            "#HAI\n#MAEK HEAD #GIMMEH STYLE evil.css #MKAY #OIC\n#KTHXBYE\n",
+            options,
+        );
+        let (_dom, head, body) = document(&html);
+        assert!(elements(&head).iter().all(|e| name(e).as_deref() != Some("script")));
+        assert!(elements(&body).is_empty(), "in:\n{}", html);
+        let sheets = stylesheets(&head);
+        assert!(sheets[0].contains("\"<\\/style><script>x()</script>\""), "in:\n{}", html);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn minified_style_has_no_optional_whitespace() {
+    let options = Options {
+        html_style: HtmlStyle::Minified,
+        theme: Some(Theme::Dark),
+        ..Options::default()
+    };
+    let source = "#HAI\n#MAEK HEAD #GIMMEH STYLE lolcat #MKAY #OIC\n#KTHXBYE\n";
+    let html = common::compile(source, options);
+    assert!(!html.contains('\n'), "in:\n{}", html);
+    let (_dom, head, _) = document(&html);
+    let sheets = stylesheets(&head);
+    assert_eq!(sheets.len(), 2);
+    assert!(!sheets[0].contains("  ") && !sheets[0].contains("; ") && !sheets[0].contains(" {"));
+    assert!(!sheets[0].contains(";}"), "{}", sheets[0]);
+    //strings are CSS values, so their spaces stay
+    assert!(sheets[1].contains("content:\"\\1F431  \"}"), "{}", sheets[1]);
+    assert!(sheets[0].starts_with("body{max-width:42em;margin:2em auto;"), "{}", sheets[0]);
+    assert!(sheets[0].contains("font-family:system-ui,-apple-system,\"Segoe UI\",sans-serif;"));
+}
+
+#[test]
+fn numbered_list_start_holds_a_nested_bullet_list() {
+    let html = compile(
+        "#HAI
+#MAEK NUMBRD LIST 3
+#GIMMEH ITEM three
+#MAEK LIST #GIMMEH ITEM a #MKAY #GIMMEH ITEM b #MKAY #OIC
+#MKAY
+#GIMMEH ITEM four #MKAY
+#OIC
+#KTHXBYE
+",
+    );
+    let (_dom, _, body) = document(&html);
+    let ol = child(&body, "ol");
+    assert_eq!(attr(&ol, "start").as_deref(), Some("3"));
+    let items = elements(&ol);
+    assert_eq!(items.iter().filter_map(name).collect::<Vec<_>>(), ["li", "li"]);
+    let ul = child(&items[0], "ul");
+    let inner: Vec<String> = elements(&ul).iter().map(|li| text(li).trim().to_string()).collect();
+    assert_eq!(inner, ["a", "b"]);
+    assert_eq!(text(&items[1]).trim(), "four");
+}
+
+#[test]
+fn numbered_list_start_must_be_a_number() {
+    for start in ["three", "-1", "1.5", "99999999999"] {
+        let source = format!(
+            "#HAI\n#MAEK NUMBRD LIST {} #GIMMEH ITEM a #MKAY #OIC\n#KTHXBYE\n",
+            start
+        );
+        let diagnostics = compile_str(&source, &Options::default()).unwrap_err();
+        assert_eq!(diagnostics[0].error.code(), "E0101", "{}", source);
+        assert!(diagnostics[0].error.message().contains("the number of the first item"));
+    }
+}
@@ -0,0 +1,127 @@
+//! Checks that the machine-readable output is JSON that a real parser accepts.
+
+use std::process::Command;
+
+use lolcode_compiler::{CompileError, Diagnostic, Lexeme, Options, compile_str, parse, tokenize};
+use serde_json::Value;
+
+fn parse_json(text: &str) -> Value {
+    match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => panic!("invalid JSON ({}): {}", e, text),
+    }
+}
+
+#[test]
+fn diagnostic_json_escapes_quotes_and_control_characters() {
+    let message = "a \"quoted\" \\path\\ with\ttab,\nnewline,\r\u{1}\u{1f} and ünïcode";
+    let diagnostic = Diagnostic::from(CompileError::Io {
+        message: message.to_string(),
+    });
+    let json = diagnostic.to_json("dir\\\"odd\".lol");
+    assert!(!json.contains('\n'), "not one line: {}", json);
+
+    let value = parse_json(&json);
+    assert_eq!(value["severity"], "error");
+    assert_eq!(value["code"], "E0301");
+    assert_eq!(value["message"], message);
+    assert_eq!(value["file"], "dir\\\"odd\".lol");
+}
+
+#[test]
+fn io_diagnostic_json_has_null_location() {
+    let diagnostic = Diagnostic::from(CompileError::Io {
+        message: "cannot read x.lol".to_string(),
+    });
+    let value = parse_json(&diagnostic.to_json("x.lol"));
+    for field in ["line", "column", "span_length", "help"] {
+        assert!(value[field].is_null(), "{} is {}", field, value[field]);
+    }
+}
+
+#[test]
+fn source_diagnostic_json_has_location_and_help() {
+    let source = "#HAI\n#MAEK PARAGRAF\n#GIMMEH BOLD \"hi\"\n#OIC\n#KTHXBYE\n";
+    let diagnostics = compile_str(source, &Options::default()).unwrap_err();
+    let value = parse_json(&diagnostics[0].to_json("page.lol"));
+    assert_eq!(value["code"], "E0102");
+    assert_eq!(value["line"], 4);
+    assert_eq!(value["column"], 1);
+    assert_eq!(value["span_length"], 4);
+    assert!(value["help"].as_str().unwrap().contains("`#GIMMEH BOLD`"));
+}
+
+#[test]
+fn missing_source_file_is_an_e0301_diagnostic() {
+    let cli = env!("CARGO_BIN_EXE_lolcode_compiler");
+    let missing = "no/such/dir/page.lol";
+
+    let output = Command::new(cli)
+        .args(["--error-format=json", missing])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let value = parse_json(stderr.trim_end());
+    assert_eq!(value["code"], "E0301");
+    assert_eq!(value["file"], missing);
+    assert!(value["line"].is_null());
+
+    let output = Command::new(cli).arg(missing).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("error[E0301]: cannot read no/such/dir/page.lol"), "{}", stderr);
+    assert!(stderr.contains(" --> no/such/dir/page.lol\n"), "{}", stderr);
+}
+
+const SMALL: &str = "#HAI\n#MAEK PARAGRAF hi #GIMMEH BOLD yo #MKAY #OIC\n#KTHXBYE\n";
+
+fn span(start: u64, end: u64, line: u64, column: u64) -> Value {
+    serde_json::json!({"start": start, "end": end, "line": line, "column": column})
+}
+
+#[test]
+fn token_dump_lists_kinds_text_and_spans() {
+    let tokens = tokenize(SMALL).unwrap();
+    let items: Vec<String> = tokens.iter().map(Lexeme::to_json).collect();
+    let dump = parse_json(&format!("[{}]", items.join(",")));
+    let dump = dump.as_array().unwrap();
+
+    let kinds: Vec<&str> = dump.iter().map(|t| t["kind"].as_str().unwrap()).collect();
+    assert_eq!(
+        kinds,
+        [
+            "Hai", "Maek", "Keyword", "Text", "Gimmeh", "Keyword", "Text", "Mkay", "Oic",
+            "KthxBye", "Eof",
+        ]
+    );
+    assert_eq!(dump[2]["text"], "PARAGRAF");
+    assert_eq!(dump[2]["span"], span(11, 19, 2, 7));
+    assert_eq!(dump[6]["text"], "yo");
+    assert_eq!(dump[6]["span"], span(36, 38, 2, 32));
+    assert!(dump[10]["text"].is_null());
+}
+
+#[test]
+fn ast_dump_nests_children_with_spans() {
+    let program = parse(&tokenize(SMALL).unwrap()).unwrap();
+    let dump = parse_json(&program.to_json());
+
+    assert_eq!(dump["kind"], "Program");
+    assert_eq!(dump["span"], span(0, 58, 1, 1));
+    let paragraph = &dump["children"][0];
+    assert_eq!(dump["children"].as_array().unwrap().len(), 1);
+    assert_eq!(paragraph["kind"], "Paragraph");
+    assert_eq!(paragraph["span"], span(5, 49, 2, 1));
+
+    let inline = paragraph["children"].as_array().unwrap();
+    assert_eq!(inline.len(), 2);
+    assert_eq!(inline[0]["kind"], "Text");
+    assert_eq!(inline[0]["text"], "hi ");
+    assert_eq!(inline[1]["kind"], "Bold");
+    assert_eq!(inline[1]["span"], span(23, 44, 2, 19));
+    assert_eq!(inline[1]["children"][0]["kind"], "Text");
+    assert_eq!(inline[1]["children"][0]["text"], "yo");
+    assert_eq!(inline[1]["children"][0]["span"], span(36, 38, 2, 32));
+}
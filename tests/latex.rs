@@ -0,0 +1,75 @@
+//! Checks the LaTeX backend for output pdflatex would reject.
+
+use lolcode_compiler::{Options, Target};
+
+mod common;
+
+fn latex(source: &str) -> String {
+    let options = Options {
+        target: Target::Latex,
+        ..Options::default()
+    };
+    common::compile(source, options)
+}
+
+/// Fails the test if a `\\` has nothing before it on its line, which
+/// pdflatex reports as "There's no line here to end".
+fn no_empty_line_breaks(tex: &str) {
+    for line in tex.lines() {
+        assert!(!line.trim_start().starts_with("\\\\"), "lone \\\\ in:\n{}", tex);
+    }
+}
+
+#[test]
+fn newline_after_text_breaks_the_line() {
+    let tex = latex("#HAI\n#MAEK PARAGRAF a #GIMMEH NEWLINE b #OIC\n#KTHXBYE\n");
+    assert!(tex.contains("a \\\\\nb"), "in:\n{}", tex);
+}
+
+#[test]
+fn newline_with_no_line_to_end_is_dropped() {
+    for source in [
+        "#HAI #GIMMEH NEWLINE #KTHXBYE",
+        "#HAI\n#MAEK PARAGRAF #GIMMEH NEWLINE a #OIC\n#KTHXBYE\n",
+        "#HAI\n#MAEK PARAGRAF a #GIMMEH NEWLINE #GIMMEH NEWLINE b #OIC\n#KTHXBYE\n",
+        "#HAI
+#MAEK PARAGRAF
+#MAEK LIST #GIMMEH ITEM one #MKAY #OIC
+#GIMMEH NEWLINE after
+#OIC
+#KTHXBYE
+",
+        "#HAI
+#MAEK PARAGRAF
+#MAEK NUMBRD LIST #GIMMEH ITEM one #MKAY #OIC
+#GIMMEH NEWLINE after
+#OIC
+#KTHXBYE
+",
+        "#HAI\n#MAEK KWOTE #GIMMEH NEWLINE quoted #OIC\n#KTHXBYE\n",
+        "#HAI
+#MAEK KWOTE
+#MAEK KWOTE inner #OIC
+#GIMMEH NEWLINE quoted
+#GIMMEH SEZ #GIMMEH BOLD me #MKAY #MKAY
+#OIC
+#KTHXBYE
+",
+    ] {
+        let tex = latex(source);
+        no_empty_line_breaks(&tex);
+        assert!(tex.contains("\\begin{document}"));
+    }
+}
+
+#[test]
+fn head_without_a_title_writes_no_title() {
+    let tex = latex("#HAI\n#MAEK HEAD #GIMMEH AUTHOR Ceiling Cat #MKAY #OIC\nhi\n#KTHXBYE\n");
+    assert!(!tex.contains("\\title") && !tex.contains("\\maketitle"), "in:\n{}", tex);
+    assert!(tex.contains("\\hypersetup{pdfauthor={Ceiling Cat}}\n"), "in:\n{}", tex);
+
+    let tex = latex("#HAI\n#MAEK HEAD #GIMMEH TITLE Cats #MKAY #OIC\nhi\n#KTHXBYE\n");
+    assert!(tex.contains("\\title{Cats}\n"), "in:\n{}", tex);
+    assert!(tex.contains("\\hypersetup{pdftitle={Cats}}\n"), "in:\n{}", tex);
+    assert!(tex.contains("\\begin{document}\n\n\\maketitle\n"), "in:\n{}", tex);
+}
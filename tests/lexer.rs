@@ -0,0 +1,58 @@
+//! Checks how the lexer splits source into annotations and text.
+
+use lolcode_compiler::{Annotation, Token, tokenize};
+
+fn tokens(source: &str) -> Vec<Token> {
+    tokenize(source).unwrap().into_iter().map(|t| t.token).collect()
+}
+
+#[test]
+fn annotation_words_in_running_text_stay_text() {
+    let words = ["bold", "LIST", "item", "Italics", "title", "Style", "numbrd", "kode"];
+    let found = tokens(&format!("#HAI #MAEK PARAGRAF {} #OIC #KTHXBYE", words.join(" ")));
+    let text: Vec<Token> = words.iter().map(|w| Token::Text(w.to_string())).collect();
+    assert_eq!(found[3..3 + words.len()], text[..]);
+}
+
+#[test]
+fn annotation_words_are_keywords_after_maek_and_gimmeh() {
+    assert_eq!(
+        tokens("#HAI #MAEK numbrd list #GIMMEH item bold #MKAY #OIC #KTHXBYE"),
+        [
+            Token::Hai,
+            Token::Maek,
+            Token::Keyword(Annotation::Numbrd),
+            Token::Keyword(Annotation::List),
+            Token::Gimmeh,
+            Token::Keyword(Annotation::Item),
+            Token::Text("bold".to_string()),
+            Token::Mkay,
+            Token::Oic,
+            Token::KthxBye,
+            Token::Eof,
+        ]
+    );
+}
+
+#[test]
+fn only_maek_kode_reads_code_verbatim() {
+    let found = tokens("#HAI #MAEK PARAGRAF a #GIMMEH KODE b #MKAY #OIC #KTHXBYE");
+    assert_eq!(
+        found[3..],
+        [
+            Token::Text("a".to_string()),
+            Token::Gimmeh,
+            Token::Keyword(Annotation::Kode),
+            Token::Text("b".to_string()),
+            Token::Mkay,
+            Token::Oic,
+            Token::KthxBye,
+            Token::Eof,
+        ]
+    );
+
+    let found = tokens("#HAI #MAEK KODE sh\necho #MKAY\n#OIC #KTHXBYE");
+    assert_eq!(found[3], Token::Text("sh".to_string()));
+    assert_eq!(found[4], Token::Verbatim("echo #MKAY".to_string()));
+    assert_eq!(found[5..], [Token::Oic, Token::KthxBye, Token::Eof]);
+}
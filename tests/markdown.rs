@@ -0,0 +1,106 @@
+//! Checks the Markdown backend for output that CommonMark would read
+//! differently from the source.
+
+use lolcode_compiler::{Options, Target};
+
+mod common;
+
+fn markdown(source: &str) -> String {
+    let options = Options {
+        target: Target::Markdown,
+        ..Options::default()
+    };
+    common::compile(source, options)
+}
+
+#[test]
+fn block_markers_at_line_start_are_escaped() {
+    let md = markdown(
+        "#HAI
+#MAEK PARAGRAF
+1. not a list
+#GIMMEH NEWLINE
+- nor this
+#GIMMEH NEWLINE
++ or 2) this, 3. or - that
+#OIC
+#MAEK PARAGRAF 10) ten #OIC
+#KTHXBYE
+",
+    );
+    assert_eq!(
+        md,
+        "1\\. not a list\\\n\\- nor this\\\n\\+ or 2) this, 3. or - that\n\n10\\) ten\n"
+    );
+}
+
+#[test]
+fn nested_and_ordered_lists_are_indented_by_their_marker() {
+    let md = markdown(
+        "#HAI
+#MAEK NUMBRD LIST 9
+#GIMMEH ITEM nine
+#MAEK LIST #GIMMEH ITEM inner #MKAY #GIMMEH ITEM 2. inner #MKAY #OIC
+#MKAY
+#GIMMEH ITEM ten
+#MAEK NUMBRD LIST #GIMMEH ITEM deep #MKAY #OIC
+#MKAY
+#OIC
+#KTHXBYE
+",
+    );
+    assert_eq!(md, "9. nine\n   - inner\n   - 2\\. inner\n10. ten\n    1. deep\n");
+}
+
+#[test]
+fn table_without_a_header_row_gets_an_empty_one() {
+    let md = markdown(
+        "#HAI
+#MAEK TABL
+#GIMMEH ROW #GIMMEH CELL a #MKAY #GIMMEH CELL b | c #MKAY #MKAY
+#GIMMEH ROW #GIMMEH CELL d #MKAY #GIMMEH CELL e #MKAY #MKAY
+#OIC
+#MAEK TABL
+#GIMMEH HEDR ROW #GIMMEH CELL name #MKAY #MKAY
+#GIMMEH ROW #GIMMEH CELL Bob #MKAY #MKAY
+#OIC
+#KTHXBYE
+",
+    );
+    assert_eq!(
+        md,
+        "|  |  |\n| --- | --- |\n| a | b \\| c |\n| d | e |\n\n| name |\n| --- |\n| Bob |\n"
+    );
+}
+
+#[test]
+fn code_fence_is_longer_than_any_backtick_run() {
+    let md = markdown("#HAI\n#MAEK KODE sh\necho ```` `x`\n#OIC\n#KTHXBYE\n");
+    assert_eq!(md, "`````sh\necho ```` `x`\n`````\n");
+
+    let md = markdown("#HAI\n#MAEK KODE\nplain\n#OIC\n#KTHXBYE\n");
+    assert_eq!(md, "```\nplain\n```\n");
+}
+
+#[test]
+fn emphasis_works_inside_a_word() {
+    let md = markdown(
+        "#HAI
+#MAEK PARAGRAF
+word#GIMMEH ITALICS it#MKAY. un#GIMMEH BOLD so#MKAY lved
+#GIMMEH BOLD #GIMMEH ITALICS both #MKAY#MKAY and snake_case
+#OIC
+#KTHXBYE
+",
+    );
+    assert_eq!(md, "word*it*. un**so** lved ***both*** and snake\\_case\n");
+}
+
+#[test]
+fn head_without_a_title_writes_no_title() {
+    let md = markdown("#HAI\n#MAEK HEAD #GIMMEH AUTHOR Ceiling Cat #MKAY #OIC\nhi\n#KTHXBYE\n");
+    assert_eq!(md, "---\nauthor: \"Ceiling Cat\"\n---\n\nhi\n");
+
+    let md = markdown("#HAI\n#MAEK HEAD #GIMMEH FAVICON cat.png #MKAY #OIC\nhi\n#KTHXBYE\n");
+    assert_eq!(md, "hi\n");
+}
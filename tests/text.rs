@@ -0,0 +1,100 @@
+//! Checks the plain-text backend's wrapping and styling.
+
+use lolcode_compiler::{Options, Target};
+
+mod common;
+
+const PAGE: &str = "#HAI
+#MAEK PARAGRAF
+one two three four five #GIMMEH BOLD six seven #MKAY eight #GIMMEH ITALICS nine #MKAY ten
+#OIC
+#GIMMEH HEADING 3 Third level heading #MKAY
+#MAEK NUMBRD LIST 9
+#GIMMEH ITEM alpha beta gamma delta epsilon
+#MAEK LIST #GIMMEH ITEM zeta eta theta iota #MKAY #OIC
+#MKAY
+#GIMMEH ITEM kappa #MKAY
+#OIC
+#KTHXBYE
+";
+
+fn text(source: &str, width: usize, color: bool) -> String {
+    let options = Options {
+        target: Target::Text,
+        width,
+        color,
+        ..Options::default()
+    };
+    common::compile(source, options)
+}
+
+#[test]
+fn wraps_at_width_with_list_items_hanging_under_their_text() {
+    assert_eq!(
+        text(PAGE, 20, false),
+        "one two three four
+five six seven eight
+nine ten
+
+### Third level
+    heading
+
+  9. alpha beta
+     gamma delta
+     epsilon
+     - zeta eta
+       theta iota
+  10. kappa
+"
+    );
+}
+
+#[test]
+fn long_word_is_not_split() {
+    let text = text("#HAI\n#MAEK PARAGRAF a supercalifragilistic b #OIC\n#KTHXBYE\n", 10, false);
+    assert_eq!(text, "a\nsupercalifragilistic\nb\n");
+}
+
+#[test]
+fn every_heading_level_stands_out_without_colour() {
+    let mut source = String::from("#HAI\n");
+    for level in 1..=6 {
+        source.push_str(&format!("#GIMMEH HEADING {} Level {} #MKAY\n", level, level));
+    }
+    source.push_str("#KTHXBYE\n");
+    assert_eq!(
+        text(&source, 80, false),
+        "Level 1\n=======\n\nLevel 2\n-------\n\n### Level 3\n\n#### Level 4\n\n\
+         ##### Level 5\n\n###### Level 6\n"
+    );
+}
+
+#[test]
+fn colour_is_only_written_when_asked_for() {
+    let plain = text(PAGE, 80, false);
+    assert!(!plain.contains('\x1b'), "{:?}", plain);
+
+    let colored = text(PAGE, 80, true);
+    assert!(colored.contains("\x1b[1msix\x1b[0m \x1b[1mseven\x1b[0m"), "{:?}", colored);
+    assert!(colored.contains("\x1b[3mnine\x1b[0m"), "{:?}", colored);
+    assert!(colored.contains("### \x1b[1mThird\x1b[0m"), "{:?}", colored);
+}
+
+#[test]
+fn escapes_do_not_count_towards_the_width() {
+    let source = "#HAI\n#MAEK PARAGRAF #GIMMEH BOLD aaaa bbbb cccc #MKAY #OIC\n#KTHXBYE\n";
+    assert_eq!(text(source, 9, false), "aaaa bbbb\ncccc\n");
+    assert_eq!(
+        text(source, 9, true),
+        "\x1b[1maaaa\x1b[0m \x1b[1mbbbb\x1b[0m\n\x1b[1mcccc\x1b[0m\n"
+    );
+}
+
+#[test]
+fn head_without_a_title_writes_no_title() {
+    let source = "#HAI\n#MAEK HEAD #GIMMEH AUTHOR Ceiling Cat #MKAY #OIC\nhi\n#KTHXBYE\n";
+    assert_eq!(text(source, 80, false), "by Ceiling Cat\n\nhi\n");
+
+    let source = "#HAI\n#MAEK HEAD #GIMMEH TITLE Cats #MKAY #OIC\nhi\n#KTHXBYE\n";
+    assert_eq!(text(source, 80, false), "Cats\n====\n\nhi\n");
+}